@@ -0,0 +1,40 @@
+//! Generates the list of days for the `aoc` runner binary from the files in `src/bin`,
+//! so that adding a new `dayN.rs` is all that's needed to register it.
+
+use std::fmt::Write;
+use std::path::Path;
+
+fn main() {
+    let bin_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bin");
+    println!("cargo:rerun-if-changed={}", bin_dir.display());
+
+    let mut days: Vec<(u32, String)> = std::fs::read_dir(&bin_dir)
+        .expect("src/bin should exist")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let stem = path.file_stem()?.to_str()?;
+            let number = stem.strip_prefix("day")?.parse().ok()?;
+            (path.extension()? == "rs").then(|| (number, stem.to_string()))
+        })
+        .collect();
+    days.sort();
+
+    let mut out = String::new();
+    for (_, name) in &days {
+        let path = bin_dir.join(format!("{name}.rs"));
+        writeln!(
+            out,
+            "#[path = {:?}]\nmod {name};",
+            path.display().to_string()
+        )
+        .unwrap();
+    }
+    writeln!(out, "\nconst DAYS: &[aoc_2024::runner::Day] = &[").unwrap();
+    for (_, name) in &days {
+        writeln!(out, "    {name}::DAY,").unwrap();
+    }
+    writeln!(out, "];").unwrap();
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    std::fs::write(Path::new(&out_dir).join("days.rs"), out).unwrap();
+}
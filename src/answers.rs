@@ -0,0 +1,229 @@
+//! A store of accepted answers for the real inputs, so that refactors can't silently change them.
+//!
+//! Answers are kept in `answers.toml` in the crate root (or the file named by `$AOC_ANSWERS`),
+//! with one table per day:
+//! ```toml
+//! [day1]
+//! part1 = "1234"
+//! part2 = "5678"
+//! ```
+//! Only this small subset of TOML is supported.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The environment variable which overrides the location of the answers file.
+pub const ANSWERS_VAR: &str = "AOC_ANSWERS";
+
+/// The answers file used when [`ANSWERS_VAR`] is not set.
+pub const DEFAULT_ANSWERS_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/answers.toml");
+
+/// The location of the answers file.
+pub fn path() -> PathBuf {
+    std::env::var_os(ANSWERS_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_ANSWERS_FILE))
+}
+
+/// The result of comparing an answer against the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// No answer has been accepted for this part yet.
+    Unknown,
+    Correct,
+    Incorrect {
+        expected: String,
+    },
+}
+
+impl Check {
+    pub const fn is_incorrect(&self) -> bool {
+        matches!(self, Self::Incorrect { .. })
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "new"),
+            Self::Correct => write!(f, "ok"),
+            Self::Incorrect { expected } => write!(f, "WRONG (expected {expected})"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Answers {
+    days: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Answers {
+    /// Parse an answers file. Errors are reported with their (1-based) line number.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut answers = Self::default();
+        let mut day = None;
+
+        for (line_no, line) in s.lines().enumerate() {
+            let line = line.trim();
+            let error = |message: &str| format!("line {}: {message}", line_no + 1);
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| error("unterminated table header"))?;
+                answers.days.entry(name.trim().to_string()).or_default();
+                day = Some(name.trim().to_string());
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected `part = \"answer\"`"))?;
+            let day = day
+                .as_ref()
+                .ok_or_else(|| error("answer given outside of a [day] table"))?;
+            let value = parse_value(value.trim()).ok_or_else(|| error("invalid value"))?;
+            answers
+                .days
+                .entry(day.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value);
+        }
+
+        Ok(answers)
+    }
+
+    /// Load the answers file, treating a missing file as an empty store.
+    pub fn load() -> io::Result<Self> {
+        match std::fs::read_to_string(path()) {
+            Ok(s) => Self::parse(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        std::fs::write(path(), self.to_string())
+    }
+
+    pub fn get(&self, day: &str, part: &str) -> Option<&str> {
+        self.days.get(day)?.get(part).map(String::as_str)
+    }
+
+    /// Accept an answer, replacing any previous answer for the same part.
+    pub fn insert(&mut self, day: &str, part: &str, answer: String) {
+        self.days
+            .entry(day.to_string())
+            .or_default()
+            .insert(part.to_string(), answer);
+    }
+
+    pub fn check(&self, day: &str, part: &str, answer: &str) -> Check {
+        match self.get(day, part) {
+            None => Check::Unknown,
+            Some(expected) if expected == answer => Check::Correct,
+            Some(expected) => Check::Incorrect {
+                expected: expected.to_string(),
+            },
+        }
+    }
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next()? {
+                '"' => break,
+                '\\' => out.push(chars.next()?),
+                c => out.push(c),
+            }
+        }
+        // Only a comment may follow the closing quote.
+        let rest = chars.as_str().trim();
+        (rest.is_empty() || rest.starts_with('#')).then_some(out)
+    } else {
+        let value = value.split('#').next()?.trim();
+        value.parse::<i128>().ok().map(|v| v.to_string())
+    }
+}
+
+/// Serialises the store, with days and parts in numeric order.
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut days: Vec<_> = self.days.iter().collect();
+        days.sort_by_key(|(name, _)| (name.len(), *name));
+
+        for (i, (day, parts)) in days.into_iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{day}]")?;
+            let mut parts: Vec<_> = parts.iter().collect();
+            parts.sort_by_key(|(name, _)| (name.len(), *name));
+            for (part, answer) in parts {
+                let escaped = answer.replace('\\', "\\\\").replace('"', "\\\"");
+                writeln!(f, "{part} = \"{escaped}\"")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# Accepted answers
+[day10]
+part1 = "7"
+
+[day2]
+part2 = 42 # a bare integer
+part1 = "say \"hi\""
+"#;
+
+    #[test]
+    fn parse_and_check() {
+        let answers = Answers::parse(SAMPLE).unwrap();
+        assert_eq!(answers.get("day2", "part1"), Some("say \"hi\""));
+        assert_eq!(answers.check("day2", "part2", "42"), Check::Correct);
+        assert_eq!(answers.check("day10", "part2", "1"), Check::Unknown);
+        assert_eq!(
+            answers.check("day10", "part1", "8"),
+            Check::Incorrect {
+                expected: "7".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut answers = Answers::parse(SAMPLE).unwrap();
+        answers.insert("day1", "part1", "x".to_string());
+
+        let written = answers.to_string();
+        assert!(written.starts_with("[day1]\npart1 = \"x\"\n\n[day2]\npart1"));
+        assert_eq!(Answers::parse(&written), Ok(answers));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Answers::parse("part1 = \"1\""),
+            Err("line 1: answer given outside of a [day] table".to_string())
+        );
+        assert_eq!(
+            Answers::parse("[day1]\npart1 = \"1"),
+            Err("line 2: invalid value".to_string())
+        );
+    }
+}
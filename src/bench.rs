@@ -0,0 +1,183 @@
+//! A small benchmarking harness for comparing implementations of the same part.
+//!
+//! Alternative implementations are registered alongside a part with `|`:
+//! ```ignore
+//! day!(part1, part2 | part2_topt);
+//! ```
+//! and are run with `cargo run --release --bin dayN -- --bench [ITERATIONS]`.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::runner::Part;
+use crate::Error;
+
+/// The number of iterations used when none is given.
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+/// Timings for a single implementation over every iteration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub iterations: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    /// The number of input bytes processed per second, based on the mean time.
+    pub throughput: f64,
+}
+
+impl Stats {
+    /// Summarise a set of timings.
+    ///
+    /// ## Panics
+    /// Panics if `times` is empty.
+    pub fn from_times(mut times: Vec<Duration>, input_len: usize) -> Self {
+        assert!(!times.is_empty(), "Can't summarise zero timings");
+        times.sort_unstable();
+
+        let iterations = times.len();
+        let mean = times.iter().sum::<Duration>() / iterations as u32;
+        Self {
+            iterations,
+            min: times[0],
+            median: times[iterations / 2],
+            mean,
+            throughput: input_len as f64 / mean.as_secs_f64(),
+        }
+    }
+}
+
+/// The result of benchmarking one implementation.
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub name: &'static str,
+    pub answer: Result<String, Error>,
+    pub stats: Stats,
+}
+
+/// The results of benchmarking a part and all of its alternatives.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub variants: Vec<Variant>,
+}
+
+impl Report {
+    /// The variants whose answer differs from that of the first (registered) implementation.
+    pub fn mismatches(&self) -> impl Iterator<Item = &Variant> {
+        let expected = &self.variants[0].answer;
+        self.variants.iter().filter(move |v| &v.answer != expected)
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.mismatches().next().is_none()
+    }
+}
+
+/// Run a single implementation `iterations` times (after one untimed warm-up run).
+pub fn bench_one(part: &Part, input: &'static str, iterations: usize) -> Variant {
+    let answer = (part.solve)(input);
+    let times = (0..iterations.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            let _ = std::hint::black_box((part.solve)(std::hint::black_box(input)));
+            start.elapsed()
+        })
+        .collect();
+
+    Variant {
+        name: part.name,
+        answer,
+        stats: Stats::from_times(times, input.len()),
+    }
+}
+
+/// Benchmark a part and each of its alternatives against the same input.
+pub fn bench_part(part: &Part, input: &'static str, iterations: usize) -> Report {
+    let variants = std::iter::once(part)
+        .chain(part.alternatives)
+        .map(|p| bench_one(p, input, iterations))
+        .collect();
+    Report { variants }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name_width = self
+            .variants
+            .iter()
+            .map(|v| v.name.len())
+            .max()
+            .unwrap_or(0);
+        let iterations = self.variants[0].stats.iterations;
+        writeln!(f, "{} ({iterations} iterations)", self.variants[0].name)?;
+
+        for v in &self.variants {
+            writeln!(
+                f,
+                "  {:<name_width$}  min {:>10.2?}  median {:>10.2?}  mean {:>10.2?}  {:>9.2} MiB/s",
+                v.name,
+                v.stats.min,
+                v.stats.median,
+                v.stats.mean,
+                v.stats.throughput / (1024.0 * 1024.0),
+            )?;
+        }
+
+        let answer = |v: &Variant| match &v.answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => format!("error: {e}"),
+        };
+        if self.is_consistent() {
+            write!(f, "  all variants agree: {}", answer(&self.variants[0]))
+        } else {
+            write!(f, "  MISMATCH: expected {}", answer(&self.variants[0]))?;
+            for v in self.mismatches() {
+                write!(f, "\n    {} returned {}", v.name, answer(v))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PART: Part = Part {
+        name: "part1",
+        solve: |input| Ok(input.len().to_string()),
+        alternatives: &[
+            Part {
+                name: "part1_chars",
+                solve: |input| Ok(input.chars().count().to_string()),
+                alternatives: &[],
+            },
+            Part {
+                name: "part1_wrong",
+                solve: |_| Err(Error::EmptyInput),
+                alternatives: &[],
+            },
+        ],
+    };
+
+    #[test]
+    fn reports_mismatched_variants() {
+        let report = bench_part(&PART, "abc", 5);
+        assert_eq!(report.variants.len(), 3);
+        assert!(report.variants.iter().all(|v| v.stats.iterations == 5));
+        assert!(!report.is_consistent());
+
+        let mismatches: Vec<_> = report.mismatches().map(|v| v.name).collect();
+        assert_eq!(mismatches, ["part1_wrong"]);
+    }
+
+    #[test]
+    fn stats() {
+        let times = [3, 1, 2, 10].map(Duration::from_millis).to_vec();
+        let stats = Stats::from_times(times, 1000);
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(3));
+        assert_eq!(stats.mean, Duration::from_millis(4));
+        assert!((stats.throughput - 250_000.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,65 @@
+//! Runs any number of days at once, printing their answers and timings as a table.
+//!
+//! Usage: `aoc [DAYS] [--part N] [--accept]`, where `DAYS` is `all` (the default),
+//! a single day, a range such as `3-5`, or a comma-separated list of these.
+//! Inputs are loaded from the default location for each day; see [`aoc_2024::input`].
+//! Answers are checked against the answers file, and `--accept` stores them in it;
+//! see [`aoc_2024::answers`]. Exits with status 1 if any part fails or gives a wrong answer.
+
+use aoc_2024::answers::Check;
+use aoc_2024::runner::{load_answers_or_exit, run_days, save_answers_or_exit, Selection, Table};
+
+include!(concat!(env!("OUT_DIR"), "/days.rs"));
+
+fn main() {
+    let mut selection = Selection::All;
+    let mut part = None;
+    let mut accept = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" | "-p" => {
+                part = args.next().and_then(|p| p.parse().ok());
+                if part.is_none() {
+                    usage_error("--part expects a part number");
+                }
+            }
+            "--accept" => accept = true,
+            days => {
+                selection = Selection::parse(days)
+                    .unwrap_or_else(|| usage_error(&format!("invalid day selection `{days}`")));
+            }
+        }
+    }
+
+    let mut answers = load_answers_or_exit();
+    let rows = run_days(DAYS, &selection, part, &answers);
+    if rows.is_empty() {
+        usage_error("no registered days match the selection");
+    }
+    println!("{}", Table(&rows));
+
+    if accept {
+        for row in &rows {
+            if let Ok(answer) = &row.answer {
+                answers.insert(row.day, row.part, answer.clone());
+            }
+        }
+        save_answers_or_exit(&answers);
+    }
+
+    let failed = rows.iter().any(|row| row.answer.is_err());
+    let incorrect = rows
+        .iter()
+        .any(|row| row.check.as_ref().is_some_and(Check::is_incorrect));
+    if failed || (incorrect && !accept) {
+        std::process::exit(1);
+    }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {message}");
+    eprintln!("usage: aoc [all | DAY | FIRST-LAST][,...] [--part N] [--accept]");
+    std::process::exit(2);
+}
@@ -1,64 +1,78 @@
 use aoc_2024::*;
 use rustc_hash::FxHashMap;
 
-day!(part1, part2);
-
-fn parse_input(input: &'static str) -> (Vec<u32>, Vec<u32>) {
-    input
-        .lines()
-        .map(str::split_whitespace)
-        .map(|mut values| {
-            let (Some(a), Some(b)) = (values.next(), values.next()) else {
-                panic!("Invalid input")
-            };
-            (a.parse::<u32>().unwrap(), b.parse::<u32>().unwrap())
-        })
-        .unzip()
+day!(part1, part2 | part2_topt);
+
+fn parse_input(input: &'static str) -> Result<(Vec<u32>, Vec<u32>), Error> {
+    let mut list_a = vec![];
+    let mut list_b = vec![];
+
+    for line in error::lines(input) {
+        let mut values = line.text.split_whitespace();
+        let a = values.next().ok_or_else(|| line.missing("a location ID"))?;
+        let b = values
+            .next()
+            .ok_or_else(|| line.missing("a second location ID"))?;
+        list_a.push(line.parse(a)?);
+        list_b.push(line.parse(b)?);
+    }
+
+    Ok((list_a, list_b))
 }
 
-fn part1(input: &'static str) -> u32 {
-    let (mut list_a, mut list_b) = parse_input(input);
+fn part1(input: &'static str) -> Result<u32, Error> {
+    let (mut list_a, mut list_b) = parse_input(input)?;
 
     list_a.sort_unstable();
     list_b.sort_unstable();
 
-    list_a.iter().zip(list_b).map(|(a, b)| a.abs_diff(b)).sum()
+    Ok(list_a.iter().zip(list_b).map(|(a, b)| a.abs_diff(b)).sum())
 }
 
-fn part2(input: &'static str) -> u32 {
-    let (list_a, list_b) = parse_input(input);
+fn part2(input: &'static str) -> Result<u32, Error> {
+    let (list_a, list_b) = parse_input(input)?;
 
-    list_a
+    Ok(list_a
         .iter()
         .map(|a| a * list_b.iter().filter(|b| a == *b).count() as u32)
-        .sum()
+        .sum())
 }
 
 // An alternate implementation of part 2, optimised to be O(n)
 // at the cost of the allocation of the FxHashMap.
-#[allow(dead_code)]
-fn part2_topt(input: &'static str) -> u32 {
-    let (list_a, list_b) = parse_input(input);
+// Compare the two with `--bench`.
+fn part2_topt(input: &'static str) -> Result<u32, Error> {
+    let (list_a, list_b) = parse_input(input)?;
 
     let mut map = FxHashMap::default();
     for b in list_b {
         *map.entry(b).or_insert(0) += 1;
     }
 
-    list_a.iter().map(|a| a * map.get(a).unwrap_or(&0)).sum()
+    Ok(list_a.iter().map(|a| a * map.get(a).unwrap_or(&0)).sum())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str = "3   4
+    const SAMPLE_INPUT: &str = "3   4
 4   3
 2   5
 1   3
 3   9
 3   3";
 
-    test_day!(test_part1 -> part1(SAMPLE_INPUT), 11);
-    test_day!(test_part2 -> part2(SAMPLE_INPUT), 31);
+    test_day!(test_part1 -> part1(SAMPLE_INPUT), Ok(11));
+    test_day!(test_part2 -> part2(SAMPLE_INPUT), Ok(31));
+
+    test_day!(
+        test_invalid_number -> part1("3   4\n4   x"),
+        Err(Error::InvalidNumber { line: 2, column: 5, .. })
+    );
+    test_day!(test_missing_field -> part2, [
+        ("3   4\n4", Err(Error::MissingField { line: 2, column: 2, .. })),
+        ("3", Err(Error::MissingField { line: 1, .. })),
+    ]);
+    test_day!(test_real_input -> DAY);
 }
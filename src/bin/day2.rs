@@ -1,6 +1,14 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use aoc_2024::error::Line;
+use aoc_2024::runner::ViewResult;
 use aoc_2024::*;
 
-day!(part1, part2);
+day!(part1, part2 | part2_naive; views: explain);
+
+/// The sizes of step between adjacent levels which are safe, in either direction.
+const SAFE_STEPS: RangeInclusive<i32> = 1..=3;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
@@ -8,9 +16,48 @@ enum Direction {
     Negative,
 }
 
+impl Direction {
+    /// The direction of a step, or `None` for a step of zero.
+    fn of(step: i32) -> Option<Self> {
+        match step.signum() {
+            1 => Some(Self::Positive),
+            -1 => Some(Self::Negative),
+            _ => None,
+        }
+    }
+}
+
+/// Why a step between two levels is unsafe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    /// The step goes the opposite way to the first step.
+    DirectionChange,
+    StepTooLarge,
+    StepTooSmall,
+    /// The level didn't change, and the step bounds don't allow that.
+    ZeroStep,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::DirectionChange => "direction change",
+            Self::StepTooLarge => "step too large",
+            Self::StepTooSmall => "step too small",
+            Self::ZeroStep => "zero step",
+        })
+    }
+}
+
+/// Whether a report is safe. Reports with fewer than two levels have no steps
+/// which could be unsafe, so they're always valid.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ReportStatus {
-    Invalid,
+    /// The step into the level at index `level` is the first unsafe one.
+    Invalid {
+        level: usize,
+        failure: Failure,
+    },
     Valid,
 }
 
@@ -20,71 +67,341 @@ impl ReportStatus {
     }
 }
 
-impl From<i32> for Direction {
-    fn from(value: i32) -> Self {
-        if value > 0 {
-            Self::Positive
-        } else {
-            Self::Negative
+/// Checks a report one level at a time, so it never needs to be collected.
+#[derive(Debug, Clone)]
+struct Validator {
+    steps: RangeInclusive<i32>,
+    last: Option<i32>,
+    direction: Option<Direction>,
+    len: usize,
+    status: ReportStatus,
+}
+
+impl Validator {
+    fn new(steps: RangeInclusive<i32>) -> Self {
+        Self {
+            steps,
+            last: None,
+            direction: None,
+            len: 0,
+            status: ReportStatus::Valid,
+        }
+    }
+
+    /// Add the next level of the report. Once an unsafe step has been found,
+    /// later levels are ignored.
+    fn push(&mut self, level: i32) {
+        if self.status.is_valid() {
+            match check_step(&self.steps, self.direction, self.last, level) {
+                Ok(direction) => {
+                    self.direction = direction;
+                    self.last = Some(level);
+                }
+                Err(failure) => {
+                    self.status = ReportStatus::Invalid {
+                        level: self.len,
+                        failure,
+                    }
+                }
+            }
         }
+        self.len += 1;
+    }
+
+    fn status(&self) -> ReportStatus {
+        self.status
     }
 }
 
-fn part1(input: &'static str) -> usize {
-    let mut vec = Vec::with_capacity(10);
-    let valid_reports = input.lines().filter(|line| {
-        let values = line.split_whitespace().map(|v| v.parse::<i32>().unwrap());
-        vec.clear();
-        vec.extend(values);
+/// Check the step from the `last` level to `level`, given the direction of the report so far.
+/// Returns the direction of the report including this step.
+fn check_step(
+    steps: &RangeInclusive<i32>,
+    direction: Option<Direction>,
+    last: Option<i32>,
+    level: i32,
+) -> Result<Option<Direction>, Failure> {
+    let Some(last) = last else {
+        return Ok(direction);
+    };
+    let step = level - last;
+    let size = step.abs();
+    match (direction, Direction::of(step)) {
+        _ if size == 0 && !steps.contains(&0) => Err(Failure::ZeroStep),
+        (Some(expected), Some(actual)) if expected != actual => Err(Failure::DirectionChange),
+        _ if size > *steps.end() => Err(Failure::StepTooLarge),
+        _ if size < *steps.start() => Err(Failure::StepTooSmall),
+        (direction, actual) => Ok(direction.or(actual)),
+    }
+}
+
+fn part1(input: &'static str) -> Result<usize, Error> {
+    let mut valid_reports = 0;
+    for line in error::lines(input) {
+        let mut validator = Validator::new(SAFE_STEPS);
+        for value in line.text.split_whitespace() {
+            validator.push(line.parse(value)?);
+        }
+
+        if validator.status().is_valid() {
+            valid_reports += 1;
+        }
+    }
+
+    Ok(valid_reports)
+}
+
+/// Parse a line into `report`, reusing its allocation. Only the naive part 2 needs the whole
+/// report at once; everything else checks the levels as they're parsed.
+fn parse_report(line: Line, report: &mut Vec<i32>) -> Result<(), Error> {
+    report.clear();
+    for value in line.text.split_whitespace() {
+        report.push(line.parse(value)?);
+    }
+    Ok(())
+}
+
+/// Whether every level changes in the same direction as the first step,
+/// by an amount within `steps`.
+fn check_report(
+    levels: impl IntoIterator<Item = i32>,
+    steps: &RangeInclusive<i32>,
+) -> ReportStatus {
+    let mut validator = Validator::new(steps.clone());
+    for level in levels {
+        validator.push(level);
+        if !validator.status().is_valid() {
+            break;
+        }
+    }
+    validator.status()
+}
+
+/// The indices of the levels removed by the problem dampener, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Removed<const K: usize> {
+    indices: [usize; K],
+    len: usize,
+}
+
+impl<const K: usize> Removed<K> {
+    const fn new() -> Self {
+        Self {
+            indices: [0; K],
+            len: 0,
+        }
+    }
+
+    fn as_slice(&self) -> &[usize] {
+        &self.indices[..self.len]
+    }
+
+    /// Remove another level, unless `K` have already been removed.
+    fn with(mut self, index: usize) -> Option<Self> {
+        *self.indices.get_mut(self.len)? = index;
+        self.len += 1;
+        Some(self)
+    }
+}
+
+/// One way of removing levels from the report so far which leaves it safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Kept<const K: usize> {
+    /// The last level which wasn't removed.
+    last: i32,
+    removed: Removed<K>,
+}
+
+/// The states of a [`Dampener`] for one number of trailing removed levels: one for each
+/// direction the report could have, including none yet.
+type ByDirection<const K: usize> = [Option<Kept<K>>; 3];
+
+/// Checks whether a report can be made safe by removing at most `K` levels, in a single pass
+/// over its levels without allocating.
+///
+/// After each level, it keeps the fewest removals which leave the report so far safe for each
+/// combination of direction and number of levels removed since the last kept level. Any two
+/// ways of removing levels with the same combination have the same last level and direction,
+/// so the one with fewer removals is always at least as good. This makes each level `O(K)`.
+#[derive(Debug, Clone)]
+struct Dampener<const K: usize> {
+    steps: RangeInclusive<i32>,
+    /// The index of the next level.
+    len: usize,
+    /// The states where the last level was kept.
+    kept: ByDirection<K>,
+    /// The states where the last `i + 1` levels were removed, but an earlier one was kept.
+    skipping: [ByDirection<K>; K],
+    /// Whether every level so far can be removed.
+    none_kept: Option<Removed<K>>,
+}
+
+/// The index of a direction in a [`ByDirection`].
+fn direction_index(direction: Option<Direction>) -> usize {
+    match direction {
+        None => 0,
+        Some(Direction::Positive) => 1,
+        Some(Direction::Negative) => 2,
+    }
+}
+
+/// Keep `state` for `direction` if it removes fewer levels than the one already there.
+fn keep_best<const K: usize>(
+    states: &mut ByDirection<K>,
+    direction: Option<Direction>,
+    state: Kept<K>,
+) {
+    let slot = &mut states[direction_index(direction)];
+    if slot.is_none_or(|best| state.removed.len < best.removed.len) {
+        *slot = Some(state);
+    }
+}
+
+impl<const K: usize> Dampener<K> {
+    fn new(steps: RangeInclusive<i32>) -> Self {
+        Self {
+            steps,
+            len: 0,
+            kept: [None; 3],
+            skipping: [[None; 3]; K],
+            none_kept: Some(Removed::new()),
+        }
+    }
+
+    /// Add the next level of the report.
+    fn push(&mut self, level: i32) {
+        const DIRECTIONS: [Option<Direction>; 3] =
+            [None, Some(Direction::Positive), Some(Direction::Negative)];
+        let index = self.len;
+        self.len += 1;
+
+        let mut kept = [None; 3];
+        let mut skipping = [[None; 3]; K];
+        if let Some(removed) = self.none_kept {
+            keep_best(
+                &mut kept,
+                None,
+                Kept {
+                    last: level,
+                    removed,
+                },
+            );
+        }
+        self.none_kept = self.none_kept.and_then(|removed| removed.with(index));
 
-        check_report(&vec).is_valid()
-    });
+        let previous = std::iter::once(&self.kept).chain(&self.skipping);
+        for (skipped, states) in previous.enumerate() {
+            for (&direction, state) in DIRECTIONS.iter().zip(states) {
+                let Some(state) = *state else {
+                    continue;
+                };
+                if let Ok(direction) = check_step(&self.steps, direction, Some(state.last), level) {
+                    let removed = state.removed;
+                    keep_best(
+                        &mut kept,
+                        direction,
+                        Kept {
+                            last: level,
+                            removed,
+                        },
+                    );
+                }
+                if let Some(removed) = state.removed.with(index) {
+                    // Having removed fewer than `K` levels, fewer than `K` can be trailing.
+                    let last = state.last;
+                    keep_best(&mut skipping[skipped], direction, Kept { last, removed });
+                }
+            }
+        }
+        (self.kept, self.skipping) = (kept, skipping);
+    }
 
-    valid_reports.count()
+    /// The fewest levels to remove to make the report safe, or `None` if it can't be made safe.
+    fn finish(&self) -> Option<Removed<K>> {
+        let states = std::iter::once(&self.kept).chain(&self.skipping);
+        let removed = states.flatten().flatten().map(|state| state.removed);
+        removed
+            .chain(self.none_kept)
+            .min_by_key(|removed| removed.len)
+    }
 }
 
-fn check_report(report: &[i32]) -> ReportStatus {
-    let direction: Direction = (report[0] - report[1]).into();
-    let mut report = report.iter();
+fn part2(input: &'static str) -> Result<usize, Error> {
+    let mut valid_reports = 0;
+    for line in error::lines(input) {
+        let mut dampener = Dampener::<1>::new(SAFE_STEPS);
+        for value in line.text.split_whitespace() {
+            dampener.push(line.parse(value)?);
+        }
 
-    let mut last = report.next().unwrap();
-    for value in report {
-        let diff: i32 = last - value;
-        if diff.abs() > 3 || diff == 0 || Direction::from(last - *value) != direction {
-            return ReportStatus::Invalid;
+        if dampener.finish().is_some() {
+            valid_reports += 1;
         }
-        last = value;
     }
 
-    ReportStatus::Valid
+    Ok(valid_reports)
 }
 
-fn part2(input: &'static str) -> usize {
+// The original implementation of part 2, which tries removing each level from a copy
+// of the report in turn. Compare the two with `--bench`.
+fn part2_naive(input: &'static str) -> Result<usize, Error> {
     let mut vec = Vec::with_capacity(10);
-    let valid_reports = input.lines().filter(|line| {
-        let values = line.split_whitespace().map(|v| v.parse::<i32>().unwrap());
-        vec.clear();
-        vec.extend(values);
+    let mut valid_reports = 0;
+    for line in error::lines(input) {
+        parse_report(line, &mut vec)?;
 
-        if check_report(&vec).is_valid() {
-            return true;
+        let valid = check_report(vec.iter().copied(), &SAFE_STEPS).is_valid()
+            || (0..vec.len()).any(|index| {
+                let mut vec2 = vec.clone();
+                vec2.remove(index);
+                check_report(vec2, &SAFE_STEPS).is_valid()
+            });
+        if valid {
+            valid_reports += 1;
         }
+    }
 
-        (0..vec.len()).any(|index| {
-            let mut vec2 = vec.clone();
-            vec2.remove(index);
-            check_report(&vec2).is_valid()
-        })
-    });
+    Ok(valid_reports)
+}
+
+/// View: why each unsafe report is unsafe, and which levels the problem dampener removes.
+fn explain(input: &'static str, _args: &[String]) -> ViewResult {
+    for line in error::lines(input) {
+        let mut validator = Validator::new(SAFE_STEPS);
+        let mut dampener = Dampener::<1>::new(SAFE_STEPS);
+        let (mut last, mut unsafe_step) = (None, None);
+        for value in line.text.split_whitespace() {
+            let level = line.parse(value)?;
+            let was_valid = validator.status().is_valid();
+            validator.push(level);
+            dampener.push(level);
+            if was_valid && !validator.status().is_valid() {
+                unsafe_step = last.map(|last| (last, level));
+            }
+            last = Some(level);
+        }
 
-    valid_reports.count()
+        let ReportStatus::Invalid { level, failure } = validator.status() else {
+            continue;
+        };
+        let (from, to) = unsafe_step.expect("an unsafe step should have two levels");
+        let dampened = match dampener.finish() {
+            Some(removed) => format!("safe without level {}", removed.as_slice()[0]),
+            None => "unsafe".to_string(),
+        };
+        println!(
+            "line {}: level {level}: {failure} ({from} -> {to}), {dampened}",
+            line.number
+        );
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str = "7 6 4 2 1
+    const SAMPLE_INPUT: &str = "7 6 4 2 1
 1 2 7 8 9
 9 7 6 2 1
 1 3 2 4 5
@@ -92,6 +409,116 @@ mod test {
 1 3 6 7 9
 ";
 
-    test_day!(test_part1 -> part1(SAMPLE_INPUT), 2);
-    test_day!(test_part2 -> part2(SAMPLE_INPUT), 4);
+    test_day!(test_part1 -> part1(SAMPLE_INPUT), Ok(2));
+    test_day!(test_part2 -> part2(SAMPLE_INPUT), Ok(4));
+    test_day!(test_part2_naive -> part2_naive(SAMPLE_INPUT), Ok(4));
+    test_day!(test_real_input -> DAY);
+
+    fn dampen<const K: usize>(
+        levels: impl IntoIterator<Item = i32>,
+        steps: &RangeInclusive<i32>,
+    ) -> Option<Removed<K>> {
+        let mut dampener = Dampener::new(steps.clone());
+        for level in levels {
+            dampener.push(level);
+        }
+        dampener.finish()
+    }
+
+    #[test]
+    fn test_dampen() {
+        let removed = |report: &[i32]| {
+            dampen::<1>(report.iter().copied(), &SAFE_STEPS).map(|r| r.as_slice().to_vec())
+        };
+        assert_eq!(removed(&[7, 6, 4, 2, 1]), Some(vec![]));
+        assert_eq!(removed(&[1, 3, 2, 4, 5]), Some(vec![1]));
+        assert_eq!(removed(&[2, 1, 2, 3, 4]), Some(vec![0]));
+        assert_eq!(removed(&[1, 2, 3, 4, 9]), Some(vec![4]));
+        assert_eq!(removed(&[1, 2, 7, 8, 9]), None);
+        assert!(matches!(
+            removed(&[8, 6, 4, 4, 1]).as_deref(),
+            Some([2] | [3])
+        ));
+
+        let removed = dampen::<2>([1, 2, 7, 8, 9], &SAFE_STEPS).unwrap();
+        assert_eq!(removed.as_slice(), [0, 1]);
+        assert!(dampen::<0>([1, 3, 2], &SAFE_STEPS).is_none());
+        assert_eq!(dampen::<1>([], &SAFE_STEPS), Some(Removed::new()));
+
+        // With zero steps allowed, the direction can be set long before the unsafe step.
+        let removed = dampen::<1>([1, 2, 2, 1, 0], &(0..=1)).unwrap();
+        assert_eq!(removed.as_slice(), [0]);
+    }
+
+    /// Compare the dampener with removing every combination of up to `K` levels.
+    fn check_dampen_exhaustively<const K: usize>(steps: RangeInclusive<i32>) {
+        for n in 0..=5 {
+            for mut code in 0..4_u32.pow(n) {
+                let report: Vec<i32> = (0..n)
+                    .map(|_| {
+                        let level = code % 4;
+                        code /= 4;
+                        level as i32
+                    })
+                    .collect();
+                let fewest = (0_u32..1 << n)
+                    .filter(|mask| mask.count_ones() as usize <= K)
+                    .filter(|mask| {
+                        let kept = (0..n as usize).filter(|i| mask & (1 << i) == 0);
+                        check_report(kept.map(|i| report[i]), &steps).is_valid()
+                    })
+                    .map(u32::count_ones)
+                    .min();
+
+                let removed = dampen::<K>(report.iter().copied(), &steps);
+                assert_eq!(
+                    removed.map(|r| r.as_slice().len() as u32),
+                    fewest,
+                    "{report:?}"
+                );
+                if let Some(removed) = removed {
+                    let kept = (0..report.len()).filter(|i| !removed.as_slice().contains(i));
+                    assert!(check_report(kept.map(|i| report[i]), &steps).is_valid());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dampen_exhaustively() {
+        check_dampen_exhaustively::<0>(SAFE_STEPS);
+        check_dampen_exhaustively::<1>(SAFE_STEPS);
+        check_dampen_exhaustively::<2>(SAFE_STEPS);
+        check_dampen_exhaustively::<1>(0..=1);
+        check_dampen_exhaustively::<2>(0..=1);
+        check_dampen_exhaustively::<1>(2..=2);
+    }
+
+    #[test]
+    fn test_step_bounds() {
+        assert!(check_report([1, 5, 9], &(4..=4)).is_valid());
+        assert!(!check_report([1, 5, 9], &SAFE_STEPS).is_valid());
+        assert!(check_report([3, 3, 2], &(0..=1)).is_valid());
+    }
+
+    #[test]
+    fn test_failures() {
+        let invalid = |level, failure| ReportStatus::Invalid { level, failure };
+        let check = |report: &[i32]| check_report(report.iter().copied(), &SAFE_STEPS);
+        assert_eq!(check(&[]), ReportStatus::Valid);
+        assert_eq!(check(&[7]), ReportStatus::Valid);
+        assert_eq!(check(&[1, 2, 7, 8, 9]), invalid(2, Failure::StepTooLarge));
+        assert_eq!(
+            check(&[1, 3, 2, 4, 5]),
+            invalid(2, Failure::DirectionChange)
+        );
+        assert_eq!(check(&[8, 6, 4, 4, 1]), invalid(3, Failure::ZeroStep));
+        assert_eq!(
+            check_report([1, 2, 4], &(2..=3)),
+            invalid(1, Failure::StepTooSmall)
+        );
+
+        // Empty lines are reports with no levels, and a single level is always safe.
+        assert_eq!(part1("1 2\n\n5\n1 5\n"), Ok(3));
+    }
 }
@@ -1,75 +1,866 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::ops::{Range, RangeInclusive};
+
+use aoc_2024::runner::ViewResult;
 use aoc_2024::*;
-use logos::Logos;
-use regex::Regex;
 
-const MUL_EXPR: &str = r"mul\((?<v1>\d{1,3}),(?<v2>\d{1,3})\)";
+day!(byte part1, byte part2 | part2_reader; views: trace, diagnose);
 
-// This realistically didn't need Logos, but it was fun to play around with!
-#[derive(Logos, Debug, PartialEq)]
-enum Token {
-    #[token("do()")]
-    ResumeParsing,
+/// An instruction which the tokenizer recognises, and how many operands it takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spec {
+    name: &'static str,
+    arity: RangeInclusive<usize>,
+}
 
-    #[token("don't()")]
-    StopParsing,
+/// The instructions which the tokenizer recognises, written as `name(1,2,3)`,
+/// and the most digits that each of their operands may have.
+#[derive(Debug, Clone)]
+struct Grammar {
+    specs: Vec<Spec>,
+    max_digits: usize,
+}
 
-    #[regex(r"mul\((?<v1>\d{1,3}),(?<v2>\d{1,3})\)")]
-    Multiply,
+/// An instruction found in the corrupted memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Instruction {
+    name: &'static str,
+    operands: Vec<u64>,
+    /// The byte offset at which the instruction starts.
+    offset: usize,
+    /// The length of the instruction in bytes.
+    len: usize,
 }
 
-day!(part1, part2);
+/// The result of matching an instruction at the start of some bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Match {
+    Found(Instruction),
+    NotFound,
+    /// The bytes ended partway through what could still be an instruction.
+    Incomplete,
+}
 
-fn part1(input: &'static str) -> u32 {
-    let re = Regex::new(MUL_EXPR).unwrap();
+impl Grammar {
+    /// The most digits an operand can have while always fitting in a `u64`.
+    const MAX_DIGITS: usize = 19;
 
-    re.captures_iter(input)
-        .map(|c| (c.name("v1").unwrap(), c.name("v2").unwrap()))
-        .map(|(v1, v2)| (v1.as_str().parse().unwrap(), v2.as_str().parse().unwrap()))
-        .map(|(v1, v2): (u32, u32)| v1 * v2)
-        .sum()
+    /// A grammar which recognises no instructions yet.
+    ///
+    /// ## Panics
+    /// Panics if `max_digits` is zero or more than [`Grammar::MAX_DIGITS`].
+    fn new(max_digits: usize) -> Self {
+        assert!(
+            (1..=Self::MAX_DIGITS).contains(&max_digits),
+            "operands must have between 1 and {} digits",
+            Self::MAX_DIGITS
+        );
+        Self {
+            specs: vec![],
+            max_digits,
+        }
+    }
+
+    /// `mul(a,b)`, `do()` and `don't()`, with operands of up to three digits.
+    fn puzzle() -> Self {
+        Self::new(3)
+            .with("mul", 2..=2)
+            .with("do", 0..=0)
+            .with("don't", 0..=0)
+    }
+
+    /// Recognise an instruction, replacing any existing one with the same name.
+    fn with(mut self, name: &'static str, arity: RangeInclusive<usize>) -> Self {
+        self = self.without(name);
+        self.specs.push(Spec { name, arity });
+        self
+    }
+
+    /// Stop recognising an instruction.
+    fn without(mut self, name: &str) -> Self {
+        self.specs.retain(|spec| spec.name != name);
+        self
+    }
+
+    /// Match any instruction at the start of `bytes`.
+    fn match_at(&self, bytes: &[u8]) -> Match {
+        let mut incomplete = false;
+        for spec in &self.specs {
+            match self.match_spec(spec, bytes) {
+                Match::Found(instruction) => return Match::Found(instruction),
+                Match::Incomplete => incomplete = true,
+                Match::NotFound => {}
+            }
+        }
+        if incomplete {
+            Match::Incomplete
+        } else {
+            Match::NotFound
+        }
+    }
+
+    /// Match one instruction at the start of `bytes`.
+    fn match_spec(&self, spec: &Spec, bytes: &[u8]) -> Match {
+        let name = spec.name.as_bytes();
+        let shared = name.len().min(bytes.len());
+        if bytes[..shared] != name[..shared] {
+            return Match::NotFound;
+        }
+
+        let mut pos = name.len();
+        match bytes.get(pos) {
+            None => return Match::Incomplete,
+            Some(b'(') => pos += 1,
+            Some(_) => return Match::NotFound,
+        }
+
+        let mut operands = vec![];
+        if bytes.get(pos) == Some(&b')') && spec.arity.contains(&0) {
+            pos += 1;
+        } else {
+            loop {
+                let digits = bytes[pos..]
+                    .iter()
+                    .take(self.max_digits + 1)
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                if digits == 0 && pos == bytes.len() {
+                    return Match::Incomplete;
+                }
+                if digits == 0 || digits > self.max_digits {
+                    return Match::NotFound;
+                }
+                let operand = std::str::from_utf8(&bytes[pos..pos + digits])
+                    .expect("digits should be ASCII")
+                    .parse()
+                    .expect("operands should fit in a u64");
+                operands.push(operand);
+                pos += digits;
+
+                match bytes.get(pos) {
+                    None => return Match::Incomplete,
+                    Some(b',') if operands.len() < *spec.arity.end() => pos += 1,
+                    Some(b')') if spec.arity.contains(&operands.len()) => {
+                        pos += 1;
+                        break;
+                    }
+                    Some(_) => return Match::NotFound,
+                }
+            }
+        }
+
+        Match::Found(Instruction {
+            name: spec.name,
+            operands,
+            offset: 0,
+            len: pos,
+        })
+    }
+
+    /// Every instruction in `bytes`, in a single pass.
+    fn tokenize<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = Instruction> + 'a {
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            while offset < bytes.len() {
+                if let Match::Found(mut instruction) = self.match_at(&bytes[offset..]) {
+                    instruction.offset = offset;
+                    offset += instruction.len;
+                    return Some(instruction);
+                }
+                offset += 1;
+            }
+            None
+        })
+    }
+
+    /// Every instruction read from `reader`, in a single pass.
+    fn tokenize_reader<R: Read>(&self, reader: R) -> Tokenizer<'_, R> {
+        Tokenizer {
+            grammar: self,
+            reader,
+            buffer: vec![],
+            start: 0,
+            consumed: 0,
+            eof: false,
+        }
+    }
 }
 
-fn part2(input: &'static str) -> u32 {
-    let re = Regex::new(MUL_EXPR).unwrap();
-    let mut lex = Token::lexer(input);
-    let mut can_run = true;
-    let mut count: u32 = 0;
+/// Finds instructions in a reader, buffering only as much as the current match needs.
+struct Tokenizer<'g, R> {
+    grammar: &'g Grammar,
+    reader: R,
+    buffer: Vec<u8>,
+    /// The position in `buffer` to match from next.
+    start: usize,
+    /// The byte offset of `buffer[0]` in the input.
+    consumed: usize,
+    eof: bool,
+}
+
+impl<R: Read> Tokenizer<'_, R> {
+    /// Read more input, discarding the bytes which have already been matched.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.start);
+        self.consumed += self.start;
+        self.start = 0;
+
+        let mut chunk = [0; 4096];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+        self.buffer.extend_from_slice(&chunk[..read]);
+        self.eof = read == 0;
+        Ok(())
+    }
+}
 
-    // while let Some(Ok(val)) = lex.next() { // this oneliner doesn't work for some reason ugh
-    while let Some(val) = lex.next() {
-        // ignore parsing errors between tokens
-        if let Ok(val) = val {
-            match val {
-                Token::ResumeParsing => {
-                    can_run = true;
+impl<R: Read> Iterator for Tokenizer<'_, R> {
+    type Item = io::Result<Instruction>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.start < self.buffer.len() {
+                match self.grammar.match_at(&self.buffer[self.start..]) {
+                    Match::Found(mut instruction) => {
+                        instruction.offset = self.consumed + self.start;
+                        self.start += instruction.len;
+                        return Some(Ok(instruction));
+                    }
+                    Match::Incomplete if !self.eof => {}
+                    _ => {
+                        self.start += 1;
+                        continue;
+                    }
                 }
-                Token::StopParsing => {
-                    can_run = false;
+            } else if self.eof {
+                return None;
+            }
+            if let Err(e) = self.fill() {
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+/// Why an instruction-like fragment was rejected by the grammar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem {
+    /// Instructions are written `name(a,b)`: with parentheses, and commas between operands.
+    BadDelimiter {
+        expected: char,
+        found: char,
+    },
+    OperandTooLong {
+        digits: usize,
+        max: usize,
+    },
+    /// Instructions can't contain whitespace.
+    Whitespace,
+    WrongArity {
+        expected: RangeInclusive<usize>,
+        found: usize,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadDelimiter { expected, found } => {
+                write!(f, "bad delimiter: expected `{expected}`, found `{found}`")
+            }
+            Self::OperandTooLong { digits, max } => {
+                write!(f, "operand too long: {digits} digits, at most {max}")
+            }
+            Self::Whitespace => write!(f, "whitespace"),
+            Self::WrongArity { expected, found } if expected.start() == expected.end() => {
+                write!(f, "expected {} operands, found {found}", expected.start())
+            }
+            Self::WrongArity { expected, found } => write!(
+                f,
+                "expected {} to {} operands, found {found}",
+                expected.start(),
+                expected.end()
+            ),
+        }
+    }
+}
+
+/// An instruction-like fragment which the grammar rejected, and the byte spans of
+/// everything wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnostic {
+    span: Range<usize>,
+    problems: Vec<(Range<usize>, Problem)>,
+}
+
+impl Grammar {
+    /// Every fragment which looks like an instruction but was rejected, in a single pass.
+    /// A fragment looks like an instruction if it's a recognised name followed by brackets
+    /// containing only numbers, separators and whitespace.
+    fn diagnose<'a>(&'a self, bytes: &'a [u8]) -> impl Iterator<Item = Diagnostic> + 'a {
+        let mut offset = 0;
+        std::iter::from_fn(move || {
+            while offset < bytes.len() {
+                let rest = &bytes[offset..];
+                if let Match::Found(instruction) = self.match_at(rest) {
+                    offset += instruction.len;
+                    continue;
                 }
-                Token::Multiply => {
-                    if can_run {
-                        let captures = re.captures(lex.slice()).unwrap();
-                        let (v1, v2) = (captures.name("v1").unwrap(), captures.name("v2").unwrap());
-                        let (v1, v2): (u32, u32) =
-                            (v1.as_str().parse().unwrap(), v2.as_str().parse().unwrap());
-                        count += v1 * v2;
+                let near_miss = self
+                    .specs
+                    .iter()
+                    .find_map(|spec| self.near_miss(spec, rest));
+                match near_miss {
+                    Some(mut diagnostic) if !diagnostic.problems.is_empty() => {
+                        diagnostic.span = offset..offset + diagnostic.span.end;
+                        for (span, _) in &mut diagnostic.problems {
+                            *span = span.start + offset..span.end + offset;
+                        }
+                        offset = diagnostic.span.end;
+                        return Some(diagnostic);
                     }
+                    _ => offset += 1,
+                }
+            }
+            None
+        })
+    }
+
+    /// Leniently parse an instruction at the start of `bytes`, finding everything wrong
+    /// with it, or `None` if it doesn't look like an instruction at all.
+    fn near_miss(&self, spec: &Spec, bytes: &[u8]) -> Option<Diagnostic> {
+        const OPEN: &[u8] = b"([{<";
+        const CLOSE: &[u8] = b")]}>";
+        const SEPARATORS: &[u8] = b",;:.";
+
+        if !bytes.starts_with(spec.name.as_bytes()) {
+            return None;
+        }
+        let mut problems = vec![];
+        let skip_whitespace = |pos: usize, problems: &mut Vec<_>| {
+            let len = bytes[pos..]
+                .iter()
+                .take_while(|b| b.is_ascii_whitespace())
+                .count();
+            if len > 0 {
+                problems.push((pos..pos + len, Problem::Whitespace));
+            }
+            pos + len
+        };
+        let delimiter = |pos: usize, expected: u8, problems: &mut Vec<_>| {
+            if bytes[pos] != expected {
+                problems.push((
+                    pos..pos + 1,
+                    Problem::BadDelimiter {
+                        expected: expected as char,
+                        found: bytes[pos] as char,
+                    },
+                ));
+            }
+        };
+
+        let mut pos = skip_whitespace(spec.name.len(), &mut problems);
+        if !OPEN.contains(bytes.get(pos)?) {
+            return None;
+        }
+        delimiter(pos, b'(', &mut problems);
+        pos = skip_whitespace(pos + 1, &mut problems);
+
+        let mut operands = 0;
+        if !CLOSE.contains(bytes.get(pos)?) {
+            loop {
+                let digits = bytes[pos..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                if digits == 0 {
+                    return None;
+                }
+                if digits > self.max_digits {
+                    problems.push((
+                        pos..pos + digits,
+                        Problem::OperandTooLong {
+                            digits,
+                            max: self.max_digits,
+                        },
+                    ));
+                }
+                operands += 1;
+                pos = skip_whitespace(pos + digits, &mut problems);
+
+                let next = *bytes.get(pos)?;
+                if CLOSE.contains(&next) {
+                    break;
+                }
+                if !SEPARATORS.contains(&next) {
+                    return None;
                 }
+                delimiter(pos, b',', &mut problems);
+                pos = skip_whitespace(pos + 1, &mut problems);
             }
         }
+        delimiter(pos, b')', &mut problems);
+        pos += 1;
+
+        if !spec.arity.contains(&operands) {
+            problems.push((
+                0..pos,
+                Problem::WrongArity {
+                    expected: spec.arity.clone(),
+                    found: operands,
+                },
+            ));
+        }
+        Some(Diagnostic {
+            span: 0..pos,
+            problems,
+        })
     }
-    count
+}
+
+fn part1(input: &'static [u8]) -> u64 {
+    Grammar::puzzle()
+        .tokenize(input)
+        .filter(|instruction| instruction.name == "mul")
+        .map(|instruction| instruction.operands.iter().product::<u64>())
+        .sum()
+}
+
+/// What an instruction does when it runs, given its operands.
+type Handler = fn(&mut State, &[u64]) -> Result<(), Rejection>;
+
+/// Why an instruction which the grammar recognised didn't run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rejection {
+    /// The instruction had been disabled.
+    Disabled,
+    /// Running the instruction would overflow the total.
+    Overflow,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Disabled => "disabled",
+            Self::Overflow => "overflow",
+        })
+    }
+}
+
+/// The state which instructions act on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct State {
+    total: i64,
+    /// The instructions which have been disabled, and so are skipped.
+    disabled: Vec<&'static str>,
+}
+
+impl State {
+    fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(&name)
+    }
+
+    fn enable(&mut self, name: &str) {
+        self.disabled.retain(|&disabled| disabled != name);
+    }
+
+    fn disable(&mut self, name: &'static str) {
+        if self.is_enabled(name) {
+            self.disabled.push(name);
+        }
+    }
+
+    /// Add `value` to the total, unless it would overflow.
+    fn add(&mut self, value: Option<i64>) -> Result<(), Rejection> {
+        self.total = value
+            .and_then(|value| self.total.checked_add(value))
+            .ok_or(Rejection::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Add the product of the operands to the total.
+fn mul(state: &mut State, operands: &[u64]) -> Result<(), Rejection> {
+    let product = operands.iter().try_fold(1_i64, |acc, &operand| {
+        acc.checked_mul(i64::try_from(operand).ok()?)
+    });
+    state.add(product)
+}
+
+/// Add the sum of the operands to the total.
+fn add(state: &mut State, operands: &[u64]) -> Result<(), Rejection> {
+    let sum = operands.iter().try_fold(0_i64, |acc, &operand| {
+        acc.checked_add(i64::try_from(operand).ok()?)
+    });
+    state.add(sum)
+}
+
+/// Subtract the sum of the operands from the total.
+fn sub(state: &mut State, operands: &[u64]) -> Result<(), Rejection> {
+    let sum = operands.iter().try_fold(0_i64, |acc, &operand| {
+        acc.checked_sub(i64::try_from(operand).ok()?)
+    });
+    state.add(sum)
+}
+
+fn enable_mul(state: &mut State, _: &[u64]) -> Result<(), Rejection> {
+    state.enable("mul");
+    Ok(())
+}
+
+fn disable_mul(state: &mut State, _: &[u64]) -> Result<(), Rejection> {
+    state.disable("mul");
+    Ok(())
+}
+
+/// Runs instructions using the handler registered for each one's name.
+#[derive(Debug, Clone)]
+struct Interpreter {
+    grammar: Grammar,
+    handlers: Vec<(&'static str, Handler)>,
+}
+
+impl Interpreter {
+    /// An interpreter which recognises no instructions yet.
+    /// See [`Grammar::new`] for the meaning of `max_digits`.
+    fn new(max_digits: usize) -> Self {
+        Self {
+            grammar: Grammar::new(max_digits),
+            handlers: vec![],
+        }
+    }
+
+    /// `mul(a,b)`, which can be turned off with `don't()` and back on with `do()`.
+    fn puzzle() -> Self {
+        Self::new(3)
+            .with("mul", 2..=2, mul)
+            .with("do", 0..=0, enable_mul)
+            .with("don't", 0..=0, disable_mul)
+    }
+
+    /// The puzzle's instructions, plus `add` and `sub` and a `mul` with up to eight operands.
+    fn extended() -> Self {
+        Self::puzzle()
+            .with("mul", 2..=8, mul)
+            .with("add", 1..=8, add)
+            .with("sub", 1..=8, sub)
+    }
+
+    /// Handle an instruction, replacing any existing handler for the same name.
+    fn with(mut self, name: &'static str, arity: RangeInclusive<usize>, handler: Handler) -> Self {
+        self.grammar = self.grammar.with(name, arity);
+        self.handlers.retain(|&(handled, _)| handled != name);
+        self.handlers.push((name, handler));
+        self
+    }
+
+    /// Run one instruction, unless it has been disabled.
+    fn step(&self, state: &mut State, instruction: &Instruction) -> Result<(), Rejection> {
+        if !state.is_enabled(instruction.name) {
+            return Err(Rejection::Disabled);
+        }
+        let (_, handler) = self
+            .handlers
+            .iter()
+            .find(|&&(name, _)| name == instruction.name)
+            .expect("the grammar should only recognise handled instructions");
+        handler(state, &instruction.operands)
+    }
+
+    /// Run the instructions, calling `on_step` with each one and whether it ran.
+    fn trace(
+        &self,
+        instructions: impl IntoIterator<Item = Instruction>,
+        mut on_step: impl FnMut(&Instruction, Result<(), Rejection>),
+    ) -> State {
+        let mut state = State::default();
+        for instruction in instructions {
+            let result = self.step(&mut state, &instruction);
+            on_step(&instruction, result);
+        }
+        state
+    }
+
+    fn run(&self, input: &[u8]) -> State {
+        self.trace(self.grammar.tokenize(input), |_, _| {})
+    }
+}
+
+/// The interpreter selected by a view's arguments: `--extended` recognises more instructions.
+fn interpreter(args: &[String]) -> Result<Interpreter, &'static str> {
+    match args {
+        [] => Ok(Interpreter::puzzle()),
+        [flag] if flag == "--extended" => Ok(Interpreter::extended()),
+        _ => Err("expected no arguments or `--extended`"),
+    }
+}
+
+/// View: every instruction with its byte offset, and whether it ran or why it didn't.
+/// With `--extended`, `add`, `sub` and longer `mul`s are recognised too.
+fn trace(input: &'static str, args: &[String]) -> ViewResult {
+    let interpreter = interpreter(args)?;
+
+    let input = input.as_bytes();
+    let tokens = interpreter.grammar.tokenize(input);
+    let state = interpreter.trace(tokens, |instruction, result| {
+        let (start, end) = (instruction.offset, instruction.offset + instruction.len);
+        let text = String::from_utf8_lossy(&input[start..end]);
+        match result {
+            Ok(()) => println!("{start:>6}: {text}"),
+            Err(rejection) => println!("{start:>6}: {text} rejected ({rejection})"),
+        }
+    });
+    println!("total: {}", state.total);
+    Ok(())
+}
+
+/// View: every fragment which looks like an instruction but was rejected, with what's
+/// wrong with it underlined, like a compiler's error listing. Takes the same arguments as `trace`.
+fn diagnose(input: &'static str, args: &[String]) -> ViewResult {
+    let interpreter = interpreter(args)?;
+    let mut count = 0;
+    for diagnostic in interpreter.grammar.diagnose(input.as_bytes()) {
+        print!("{}", render(input, &diagnostic));
+        count += 1;
+    }
+    println!("{count} rejected");
+    Ok(())
+}
+
+/// Render a diagnostic, e.g.
+///
+/// ```text
+/// rejected `mul(32,64]` at 1:38 (bytes 37..47)
+///   |
+///   | mul(32,64]
+///   |          ^ bad delimiter: expected `)`, found `]`
+/// ```
+fn render(input: &str, diagnostic: &Diagnostic) -> String {
+    let Range { start, end } = diagnostic.span.clone();
+    let before = &input[..start];
+    let line = before.matches('\n').count() + 1;
+    let column = start - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    // Show any newlines in the fragment as spaces, so that the spans still line up.
+    let fragment = input[start..end].replace(|c: char| c.is_whitespace(), " ");
+
+    let mut out = format!("rejected `{fragment}` at {line}:{column} (bytes {start}..{end})\n");
+    out += "  |\n";
+    out += &format!("  | {fragment}\n");
+    // Underline every span with the same message on one line.
+    let mut messages: Vec<String> = vec![];
+    for (_, problem) in &diagnostic.problems {
+        let message = problem.to_string();
+        if !messages.contains(&message) {
+            messages.push(message);
+        }
+    }
+    for message in messages {
+        let mut underline = vec![b' '; end - start];
+        for (span, problem) in &diagnostic.problems {
+            if problem.to_string() == message {
+                underline[span.start - start..span.end - start].fill(b'^');
+            }
+        }
+        let underline = String::from_utf8(underline).expect("the underline should be ASCII");
+        out += &format!("  | {} {message}\n", underline.trim_end());
+    }
+    out
+}
+
+fn part2(input: &'static [u8]) -> i64 {
+    Interpreter::puzzle().run(input).total
+}
+
+// An alternate implementation which streams the input through a reader, as it would
+// a file or stdin. Compare the two with `--bench`.
+fn part2_reader(input: &'static [u8]) -> i64 {
+    let interpreter = Interpreter::puzzle();
+    let instructions = interpreter
+        .grammar
+        .tokenize_reader(input)
+        .map(|instruction| instruction.expect("reading from a slice can't fail"));
+    interpreter.trace(instructions, |_, _| {}).total
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str =
-        "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
-    const SAMPLE_INPUT_2: &'static str =
-        "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+    const SAMPLE_INPUT: &[u8] =
+        b"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+    const SAMPLE_INPUT_2: &[u8] =
+        b"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
 
     test_day!(test_part1 -> part1(SAMPLE_INPUT), 161);
     test_day!(test_part2 -> part2(SAMPLE_INPUT_2), 48);
+    test_day!(test_part2_reader -> part2_reader(SAMPLE_INPUT_2), 48);
+    test_day!(test_real_input -> DAY);
+
+    /// A reader which returns at most `n` bytes at a time, to split instructions across reads.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.1.min(buf.len()).min(self.0.len());
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let grammar = Grammar::puzzle();
+        let found: Vec<_> = grammar
+            .tokenize(SAMPLE_INPUT_2)
+            .map(|i| (i.name, i.operands, i.offset))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("mul", vec![2, 4], 1),
+                ("don't", vec![], 20),
+                ("mul", vec![5, 5], 28),
+                ("mul", vec![11, 8], 48),
+                ("do", vec![], 59),
+                ("mul", vec![8, 5], 64),
+            ]
+        );
+
+        for n in 1..8 {
+            let streamed: Vec<_> = grammar
+                .tokenize_reader(Trickle(SAMPLE_INPUT_2, n))
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(
+                streamed,
+                grammar.tokenize(SAMPLE_INPUT_2).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_grammar() {
+        let operands = |grammar: &Grammar, input: &[u8]| -> Vec<Vec<u64>> {
+            grammar.tokenize(input).map(|i| i.operands).collect()
+        };
+        let input = b"mul(1234,5)mul(mul(1,2)add(1,2,3)mul(4,5,6)do(1)";
+        assert_eq!(operands(&Grammar::puzzle(), input), [vec![1, 2]]);
+
+        let wide = Grammar::new(4).with("mul", 2..=3).with("add", 1..=3);
+        assert_eq!(
+            operands(&wide, input),
+            [vec![1234, 5], vec![1, 2], vec![1, 2, 3], vec![4, 5, 6]]
+        );
+        assert_eq!(operands(&wide.without("mul"), input), [vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn test_interpreter() {
+        let interpreter = Interpreter::extended();
+        let input = b"mul(2,3,4)add(1,2)don't()sub(5)mul(10,10)do()add(7)mul(2,2)";
+        let mut steps = vec![];
+        let state = interpreter.trace(interpreter.grammar.tokenize(input), |i, result| {
+            steps.push((i.name, i.offset, result))
+        });
+        // Only `mul` is turned off by `don't()`.
+        assert_eq!(state.total, 24 + 3 - 5 + 7 + 4);
+        let rejected: Vec<_> = steps
+            .iter()
+            .filter(|(.., result)| result.is_err())
+            .collect();
+        assert_eq!(rejected, [&("mul", 31, Err(Rejection::Disabled))]);
+
+        let state = Interpreter::puzzle().run(b"don't()mul(1,2)add(1,2)");
+        assert_eq!(state.disabled, ["mul"]);
+        assert_eq!(state.total, 0);
+    }
+
+    #[test]
+    fn test_handlers() {
+        fn once(state: &mut State, _: &[u64]) -> Result<(), Rejection> {
+            state.disable("once");
+            state.add(Some(i64::MAX - 1))
+        }
+
+        let interpreter = Interpreter::new(3)
+            .with("mul", 2..=2, mul)
+            .with("once", 0..=0, once);
+        let mut rejected = vec![];
+        let state = interpreter.trace(
+            interpreter
+                .grammar
+                .tokenize(b"mul(1,1)once()once()mul(1,1)mul(1,1)"),
+            |i, result| rejected.extend(result.err().map(|r| (i.offset, r))),
+        );
+        assert_eq!(state.total, i64::MAX);
+        assert_eq!(
+            rejected,
+            [
+                (14, Rejection::Disabled),
+                (20, Rejection::Overflow),
+                (28, Rejection::Overflow)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let input = "mul(1234,5)mul ( 2 , 4 )mul[3,7]!mul(32,64]mul(1;2)do_not()mul(2,4)mul(1,2,3)";
+        let diagnostics: Vec<_> = Grammar::puzzle().diagnose(input.as_bytes()).collect();
+        let spans: Vec<_> = diagnostics.iter().map(|d| d.span.clone()).collect();
+        assert_eq!(spans, [0..11, 11..24, 24..32, 33..43, 43..51, 67..77]);
+        let starts: Vec<Vec<usize>> = diagnostics
+            .iter()
+            .map(|d| d.problems.iter().map(|(span, _)| span.start).collect())
+            .collect();
+        assert_eq!(
+            starts,
+            [
+                vec![4],
+                vec![14, 16, 18, 20, 22],
+                vec![27, 31],
+                vec![42],
+                vec![48],
+                vec![67]
+            ]
+        );
+        assert_eq!(
+            diagnostics[5].problems,
+            [(
+                67..77,
+                Problem::WrongArity {
+                    expected: 2..=2,
+                    found: 3
+                }
+            )]
+        );
+
+        let diagnostic = Grammar::puzzle().diagnose(b"x\nmul(1,2]").next().unwrap();
+        assert_eq!(
+            render("x\nmul(1,2]", &diagnostic),
+            "rejected `mul(1,2]` at 2:1 (bytes 2..10)
+  |
+  | mul(1,2]
+  |        ^ bad delimiter: expected `)`, found `]`
+"
+        );
+        let diagnostic = Grammar::puzzle().diagnose(b"mul[1234,5 ]").next().unwrap();
+        assert_eq!(
+            render("mul[1234,5 ]", &diagnostic),
+            "rejected `mul[1234,5 ]` at 1:1 (bytes 0..12)
+  |
+  | mul[1234,5 ]
+  |    ^ bad delimiter: expected `(`, found `[`
+  |     ^^^^ operand too long: 4 digits, at most 3
+  |           ^ whitespace
+  |            ^ bad delimiter: expected `)`, found `]`
+"
+        );
+    }
 }
@@ -1,104 +1,263 @@
+use std::collections::BTreeSet;
+
+use aoc_2024::order::{Cycle, RuleOrder};
+use aoc_2024::runner::ViewResult;
 use aoc_2024::*;
+use rustc_hash::FxHashSet;
 
-day!(part1, part2);
+day!(part1, part2; views: report, dot);
 
-fn part1(input: &'static str) -> u32 {
-    let mut lines = input.lines();
-    let mut pairs: Vec<(u32, u32)> = vec![];
-    let mut sets: Vec<Vec<u32>> = vec![];
-    let mut results = vec![];
+/// An update, and the line it was given on.
+struct Update {
+    line: usize,
+    pages: Vec<u32>,
+}
 
-    for line in lines.by_ref() {
-        if line.is_empty() {
-            break;
+impl Update {
+    fn middle(&self) -> u32 {
+        self.pages[self.pages.len() / 2]
+    }
+}
+
+/// The ordering rules: `X|Y` means page `X` must be printed before page `Y`.
+type Rules = RuleOrder<u32>;
+
+/// Every rule, in order.
+fn pairs(rules: &Rules) -> BTreeSet<(u32, u32)> {
+    rules.pairs().map(|(&a, &b)| (a, b)).collect()
+}
+
+/// Every page mentioned by a rule, in order.
+fn pages(rules: &Rules) -> Vec<u32> {
+    let pages: BTreeSet<u32> = rules.pairs().flat_map(|(&a, &b)| [a, b]).collect();
+    pages.into_iter().collect()
+}
+
+/// What the rules say about a single update.
+#[derive(Debug, PartialEq, Eq)]
+struct Analysis {
+    /// The first rule the update breaks, if any.
+    violation: Option<(u32, u32)>,
+    /// The number of pairs of pages which the rules don't order, even transitively.
+    unordered: usize,
+    /// The pages in a valid order, or a cycle of contradictory rules between them.
+    sorted: Result<Vec<u32>, Cycle<u32>>,
+    /// Every page which is in the middle of at least one valid order.
+    middles: Vec<u32>,
+}
+
+impl Analysis {
+    fn new(rules: &Rules, pages: &[u32]) -> Self {
+        let after = successors(rules, pages);
+        let ordered =
+            |i: usize, j: usize| after[i].contains(&pages[j]) || after[j].contains(&pages[i]);
+        let unordered = (0..pages.len())
+            .flat_map(|i| (i + 1..pages.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| !ordered(i, j))
+            .count();
+
+        let sorted = rules.sort(pages);
+        // A page can be placed anywhere between its predecessors and its successors,
+        // so it can be in the middle if neither of those is more than half the pages.
+        let middle = pages.len() / 2;
+        let middles = match &sorted {
+            Ok(_) => (0..pages.len())
+                .filter(|&i| {
+                    let before = after.iter().filter(|a| a.contains(&pages[i])).count();
+                    before <= middle && after[i].len() < pages.len() - middle
+                })
+                .map(|i| pages[i])
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        Self {
+            violation: rules.first_violation(pages).map(|(&a, &b)| (a, b)),
+            unordered,
+            sorted,
+            middles,
         }
-        let chars: Vec<&str> = line.split('|').collect();
-        pairs.push((chars[0].parse().unwrap(), chars[1].parse().unwrap()));
     }
-    for line in lines {
-        sets.push(line.split(',').map(|s| s.parse().unwrap()).collect());
+
+    /// Whether the rules between the pages define exactly one valid order.
+    fn is_total(&self) -> bool {
+        self.unordered == 0 && self.sorted.is_ok()
     }
+}
 
-    for set in sets {
-        if correctly_ordered(&set, &pairs) {
-            results.push(set[set.len() / 2]);
-        }
+/// The pages which must (transitively) come after each page, using only the rules between them.
+fn successors(rules: &Rules, pages: &[u32]) -> Vec<FxHashSet<u32>> {
+    pages
+        .iter()
+        .map(|&from| {
+            let mut seen = FxHashSet::default();
+            let mut stack = vec![from];
+            while let Some(page) = stack.pop() {
+                for &next in pages {
+                    if rules.precedes(&page, &next) && seen.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            seen
+        })
+        .collect()
+}
+
+/// Format a list of pages, e.g. `1 -> 2 -> 3`.
+fn join(pages: &[u32], separator: &str) -> String {
+    let pages: Vec<_> = pages.iter().map(u32::to_string).collect();
+    pages.join(separator)
+}
+
+/// View: whether the rules are consistent, and for each update: the first rule it breaks,
+/// whether its rules define a single order, and which pages could be in the middle.
+fn report(input: &'static str, _args: &[String]) -> ViewResult {
+    let (rules, updates) = parse_input(input)?;
+
+    let pages = pages(&rules);
+    let pairs = pairs(&rules).len();
+    match rules.sort(&pages) {
+        Ok(_) => println!("{pairs} rules over {} pages, acyclic", pages.len()),
+        Err(cycle) => println!("{pairs} rules over {} pages: {cycle}", pages.len()),
     }
 
-    results.into_iter().sum()
+    for update in &updates {
+        let analysis = Analysis::new(&rules, &update.pages);
+        let mut notes = vec![];
+        notes.push(match analysis.violation {
+            None => "valid".to_string(),
+            Some((a, b)) => format!("breaks {a}|{b}"),
+        });
+        match &analysis.sorted {
+            Err(cycle) => notes.push(cycle.to_string()),
+            Ok(_) if analysis.is_total() => notes.push("total order".to_string()),
+            Ok(_) => notes.push(format!("{} pairs unordered", analysis.unordered)),
+        }
+        match analysis.middles.as_slice() {
+            [] => {}
+            [middle] => notes.push(format!("middle {middle}")),
+            middles => notes.push(format!("middle could be {}", join(middles, ", "))),
+        }
+        println!(
+            "line {}: {}: {}",
+            update.line,
+            join(&update.pages, ","),
+            notes.join(", ")
+        );
+    }
+    Ok(())
 }
 
-fn correctly_ordered(set: &[u32], pairs: &[(u32, u32)]) -> bool {
-    for first_number in set {
-        for second_number in set {
-            if pairs
+/// View: the rule graph in Graphviz DOT format, with any rule broken by an update drawn in red.
+/// Given a line number, only the rules between that update's pages are drawn.
+fn dot(input: &'static str, args: &[String]) -> ViewResult {
+    let (rules, updates) = parse_input(input)?;
+
+    let updates: Vec<&Update> = match args {
+        [] => updates.iter().collect(),
+        [line] => {
+            let line: usize = line.parse().map_err(|_| format!("invalid line `{line}`"))?;
+            let update = updates
                 .iter()
-                .filter(|p| p.0 == *second_number && p.1 == *first_number)
-                .count()
-                > 0
-            {
-                return false;
+                .find(|u| u.line == line)
+                .ok_or_else(|| format!("no update on line {line}"))?;
+            vec![update]
+        }
+        _ => return Err("expected at most one line number".into()),
+    };
+
+    let mut broken = BTreeSet::new();
+    for update in &updates {
+        let pages = &update.pages;
+        for (i, &b) in pages.iter().enumerate() {
+            for &a in &pages[i + 1..] {
+                if rules.precedes(&a, &b) {
+                    broken.insert((a, b));
+                }
             }
         }
     }
-    true
+
+    let mut pairs = pairs(&rules);
+    if let [update] = updates.as_slice() {
+        pairs.retain(|(a, b)| update.pages.contains(a) && update.pages.contains(b));
+    }
+
+    println!("digraph rules {{");
+    for (a, b) in pairs {
+        if broken.contains(&(a, b)) {
+            println!("    {a} -> {b} [color=red, penwidth=2];");
+        } else {
+            println!("    {a} -> {b};");
+        }
+    }
+    println!("}}");
+    Ok(())
 }
 
-fn part2(input: &'static str) -> u32 {
-    let mut lines = input.lines();
+/// Parse the `X|Y` ordering rules and the comma-separated updates that follow them.
+fn parse_input(input: &'static str) -> Result<(Rules, Vec<Update>), Error> {
+    let mut lines = error::lines(input);
     let mut pairs: Vec<(u32, u32)> = vec![];
-    let mut sets: Vec<Vec<u32>> = vec![];
-    let mut results: Vec<u32> = vec![];
+    let mut updates = vec![];
 
     for line in lines.by_ref() {
-        if line.is_empty() {
+        if line.text.is_empty() {
             break;
         }
-        let chars: Vec<&str> = line.split('|').collect();
-        pairs.push((chars[0].parse().unwrap(), chars[1].parse().unwrap()));
+        let (a, b) = line.split_once("|")?;
+        pairs.push((line.parse(a)?, line.parse(b)?));
     }
     for line in lines {
-        sets.push(line.split(',').map(|s| s.parse().unwrap()).collect());
+        updates.push(Update {
+            line: line.number,
+            pages: line
+                .text
+                .split(',')
+                .map(|s| line.parse(s))
+                .collect::<Result<_, _>>()?,
+        });
     }
 
-    for mut set in sets {
-        if correctly_ordered(&set, &pairs) {
-            // only concerned about incorrectly ordered.
-            // Potential place for optimisation: we iterate
-            // the Vec twice because of this check
-            continue;
-        }
+    Ok((Rules::new(pairs), updates))
+}
 
-        // Essentially a modified bubble sort :3
-        // Tried testing all permutations but some lines have 20+ elements -> on the order of 10^18 permutations
-        let mut swapped = true;
-        let mut last_sort = set.len();
-        while swapped {
-            swapped = false;
-            for i in 1..last_sort {
-                if pairs
-                    .iter()
-                    .filter(|p| p.0 == set[i] && p.1 == set[i - 1])
-                    .count()
-                    > 0
-                {
-                    set.swap(i - 1, i);
-                    swapped = true;
-                }
-            }
-            last_sort -= 1;
+fn part1(input: &'static str) -> Result<u32, Error> {
+    let (rules, updates) = parse_input(input)?;
+
+    Ok(updates
+        .iter()
+        .filter(|update| rules.is_ordered(&update.pages))
+        .map(Update::middle)
+        .sum())
+}
+
+fn part2(input: &'static str) -> Result<u32, Error> {
+    let (rules, updates) = parse_input(input)?;
+    let mut total = 0;
+
+    for update in updates {
+        if rules.is_ordered(&update.pages) {
+            continue;
         }
-        results.push(set[set.len() / 2]);
+        let pages = rules
+            .sort(&update.pages)
+            .map_err(|cycle| Error::Contradiction {
+                line: update.line,
+                reason: cycle.to_string(),
+            })?;
+        total += pages[pages.len() / 2];
     }
 
-    results.into_iter().sum()
+    Ok(total)
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str = "47|53
+    const SAMPLE_INPUT: &str = "47|53
 97|13
 97|61
 97|47
@@ -127,6 +286,54 @@ mod test {
 61,13,29
 97,13,75,29,47";
 
-    test_day!(test_part1 -> part1(SAMPLE_INPUT), 143);
-    test_day!(test_part2 -> part2(SAMPLE_INPUT), 123);
+    test_day!(test_part1 -> part1(SAMPLE_INPUT), Ok(143));
+    test_day!(test_part2 -> part2(SAMPLE_INPUT), Ok(123));
+    // An update which follows a rule is ordered; one which breaks it isn't.
+    test_day!(test_rule_direction -> part1("1|2\n\n1,2,3\n2,1,3"), Ok(2));
+    test_day!(test_real_input -> DAY);
+    test_day!(
+        test_cycle -> part2("1|2\n2|3\n3|1\n\n1,2\n3,2,1"),
+        Err(Error::Contradiction { line: 6, .. })
+    );
+
+    #[test]
+    fn test_analysis() {
+        let (rules, updates) = parse_input(SAMPLE_INPUT).unwrap();
+        let analyses: Vec<_> = updates
+            .iter()
+            .map(|u| Analysis::new(&rules, &u.pages))
+            .collect();
+
+        assert!(analyses.iter().all(Analysis::is_total));
+        assert_eq!(analyses[0].violation, None);
+        assert_eq!(analyses[3].violation, Some((97, 75)));
+        assert_eq!(analyses[5].violation, Some((75, 13)));
+        assert_eq!(analyses[5].middles, [47]);
+        assert!(rules.sort(&pages(&rules)).is_ok());
+
+        // 1 and 3 are unordered, so either could be in the middle.
+        let rules = Rules::new([(1, 2), (3, 2), (2, 4)]);
+        let analysis = Analysis::new(&rules, &[1, 3, 2, 4]);
+        assert!(!analysis.is_total());
+        assert_eq!(analysis.unordered, 1);
+        assert_eq!(analysis.middles, [2]);
+        let analysis = Analysis::new(&rules, &[1, 3, 2]);
+        assert_eq!(analysis.middles, [1, 3]);
+    }
+
+    #[test]
+    fn test_sort() {
+        let (rules, _) = parse_input(SAMPLE_INPUT).unwrap();
+        assert!(rules.is_ordered(&[75, 47, 61, 53, 29]));
+        assert!(!rules.is_ordered(&[97, 13, 75, 29, 47]));
+        assert_eq!(
+            rules.sort(&[97, 13, 75, 29, 47]),
+            Ok(vec![97, 75, 47, 29, 13])
+        );
+
+        assert_eq!(
+            part2("1|2\n2|3\n3|1\n\n2,1,3").unwrap_err().to_string(),
+            "line 5: the rules form a cycle: 3 -> 1 -> 2 -> 3"
+        );
+    }
 }
@@ -1,12 +1,22 @@
-use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::Duration;
 
+use aoc_2024::grid::{Grid, Point};
+use aoc_2024::runner::ViewResult;
 use aoc_2024::*;
 
-day!(part1, part2);
+day!(part1, part2 | part2_serial | part2_naive; views: path, animate, loops);
+
+/// The environment variable which sets the number of threads used by part 2.
+/// Defaults to the available parallelism.
+const THREADS_VAR: &str = "AOC_THREADS";
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Tile {
-    Guard,
+    /// A guard's starting position, facing the given direction.
+    Guard(Direction),
     Obstacle,
     None,
 }
@@ -29,12 +39,20 @@ impl Direction {
         }
     }
 
-    pub const fn translation(self) -> (isize, isize) {
+    pub const fn rotate_left(self) -> Self {
+        self.rotate().rotate().rotate()
+    }
+
+    pub const fn reverse(self) -> Self {
+        self.rotate().rotate()
+    }
+
+    pub const fn translation(self) -> Point {
         match self {
-            Self::North => (0, -1),
-            Self::East => (1, 0),
-            Self::South => (0, 1),
-            Self::West => (-1, 0),
+            Self::North => Point::new(0, -1),
+            Self::East => Point::new(1, 0),
+            Self::South => Point::new(0, 1),
+            Self::West => Point::new(-1, 0),
         }
     }
 }
@@ -44,7 +62,10 @@ impl TryFrom<char> for Tile {
 
     fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
-            '^' => Ok(Self::Guard),
+            '^' => Ok(Self::Guard(Direction::North)),
+            '>' => Ok(Self::Guard(Direction::East)),
+            'v' => Ok(Self::Guard(Direction::South)),
+            '<' => Ok(Self::Guard(Direction::West)),
             '#' => Ok(Self::Obstacle),
             '.' => Ok(Self::None),
             _ => Err(()),
@@ -52,156 +73,845 @@ impl TryFrom<char> for Tile {
     }
 }
 
-#[derive(Debug)]
-struct Grid<T> {
-    pub width: usize,
-    pub data: Vec<T>,
+/// A guard's position and the direction it is facing.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct Guard {
+    position: Point,
+    direction: Direction,
+    /// How far through its [`TurnStrategy`] the guard is, from `0` to its period.
+    phase: usize,
 }
 
-impl<T> Grid<T> {
-    pub const fn new(width: usize, data: Vec<T>) -> Self {
-        Self { width, data }
+/// A single turn, made when a guard meets an obstacle.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Turn {
+    Right,
+    Left,
+    Reverse,
+}
+
+impl Turn {
+    const fn apply(self, direction: Direction) -> Direction {
+        match self {
+            Self::Right => direction.rotate(),
+            Self::Left => direction.rotate_left(),
+            Self::Reverse => direction.reverse(),
+        }
     }
+}
 
-    pub fn at_index(&self, index: usize) -> Option<&T> {
-        self.data.get(index)
+impl std::str::FromStr for Turn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "right" => Ok(Self::Right),
+            "left" => Ok(Self::Left),
+            "reverse" => Ok(Self::Reverse),
+            _ => Err(format!("unknown turn `{s}`")),
+        }
     }
+}
 
-    pub fn at(&self, pos: (isize, isize)) -> Option<&T> {
-        self.at_index(self.coord_to_index(pos))
+/// How a guard decides which way to face when it meets an obstacle.
+///
+/// A strategy may depend on how many turns the guard has made, as long as it repeats
+/// every `period` turns: the phase is part of the guard's state, so that loops are
+/// only detected once the guard is back in the same position, direction *and* phase.
+trait TurnStrategy {
+    /// The number of turns after which the strategy repeats itself.
+    fn period(&self) -> usize;
+    /// The direction to face after meeting an obstacle in the given phase.
+    fn turn(&self, direction: Direction, phase: usize) -> Direction;
+}
+
+/// Always make the same turn.
+impl TurnStrategy for Turn {
+    fn period(&self) -> usize {
+        1
     }
 
-    pub fn set(&mut self, pos: (isize, isize), value: T) {
-        let ind = self.coord_to_index(pos);
-        self.data[ind] = value;
+    fn turn(&self, direction: Direction, _phase: usize) -> Direction {
+        self.apply(direction)
     }
+}
 
-    pub fn coord_to_index(&self, pos: (isize, isize)) -> usize {
-        assert!(
-            pos.0 >= 0 && pos.1 >= 0,
-            "Invalid values provided to coord_to_index"
-        );
-        pos.1 as usize * self.width + pos.0 as usize
+/// Make each turn in order, starting again from the first after the last.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Pattern(Vec<Turn>);
+
+impl Pattern {
+    /// Returns `None` if `turns` is empty.
+    fn new(turns: Vec<Turn>) -> Option<Self> {
+        (!turns.is_empty()).then_some(Self(turns))
+    }
+}
+
+impl TurnStrategy for Pattern {
+    fn period(&self) -> usize {
+        self.0.len()
     }
 
-    #[allow(dead_code)]
-    pub fn index_to_coord(&self, ind: usize) -> (isize, isize) {
-        ((ind % self.width) as isize, (ind / self.width) as isize)
+    fn turn(&self, direction: Direction, phase: usize) -> Direction {
+        self.0[phase].apply(direction)
     }
 }
 
-fn part1(input: &'static str) -> usize {
-    let mut lines = input.lines().peekable();
-    let width = lines.peek().unwrap().len();
-    let mut grid: Grid<Tile> = Grid::new(width, vec![]);
+/// What happens when a guard walks off the edge of the map.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Edges {
+    /// The guard leaves, ending its walk.
+    Exit,
+    /// The guard reappears on the opposite edge, as if the map were a torus.
+    Wrap,
+}
+
+/// The rules a guard walks by.
+#[derive(Clone, Copy)]
+struct Rules<'a> {
+    turning: &'a dyn TurnStrategy,
+    edges: Edges,
+}
+
+impl Rules<'_> {
+    /// The rules from the puzzle: turn right at obstacles, and leave at the edges.
+    const PUZZLE: Rules<'static> = Rules {
+        turning: &Turn::Right,
+        edges: Edges::Exit,
+    };
+}
+
+/// The rules given to a view with `--turn TURNS` and `--wrap`.
+/// `TURNS` is `right`, `left` or `reverse`, or a comma-separated pattern of them.
+struct RuleArgs {
+    turning: Box<dyn TurnStrategy>,
+    edges: Edges,
+    /// The view's other arguments.
+    rest: Vec<String>,
+}
+
+impl RuleArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self {
+            turning: Box::new(Turn::Right),
+            edges: Edges::Exit,
+            rest: vec![],
+        };
 
-    for line in lines {
-        for char in line.chars() {
-            grid.data.push(char.try_into().unwrap());
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--wrap" => parsed.edges = Edges::Wrap,
+                "--turn" => {
+                    let turns = args.next().ok_or("expected turns after `--turn`")?;
+                    let turns = turns.split(',').map(str::parse).collect::<Result<_, _>>()?;
+                    let pattern = Pattern::new(turns).ok_or("expected at least one turn")?;
+                    parsed.turning = Box::new(pattern);
+                }
+                _ => parsed.rest.push(arg.clone()),
+            }
         }
+        Ok(parsed)
     }
 
-    let mut visited_positions = HashSet::new();
-    let mut direction = Direction::North;
-    let guard_pos = grid.data.iter().position(|t| *t == Tile::Guard).unwrap();
-    let mut guard_pos = ((guard_pos % width) as isize, (guard_pos / width) as isize);
+    fn rules(&self) -> Rules<'_> {
+        Rules {
+            turning: &*self.turning,
+            edges: self.edges,
+        }
+    }
+}
 
-    loop {
-        let next_tile = (
-            guard_pos.0 + direction.translation().0,
-            guard_pos.1 + direction.translation().1,
-        );
-        // println!("{:?} => {:?}", guard_pos, next_tile);
-        if next_tile.0 < 0
-            || next_tile.1 < 0
-            || next_tile.0 as usize >= width
-            || next_tile.1 as usize >= grid.data.len() / width
-        {
-            break;
+/// Why a simulation ended.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Outcome {
+    /// The guard walked off the map after `steps` moves, covering `visited` distinct tiles
+    /// (including the starting tile).
+    Exit { steps: usize, visited: usize },
+    /// The guard returned to a state it had already been in. `start` is the first state
+    /// on the cycle, and `period` is the number of states (turns included) in the cycle.
+    Loop {
+        start: Guard,
+        period: usize,
+        visited: usize,
+    },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exit { steps, visited } => {
+                write!(f, "leaves after {steps} steps, visiting {visited} tiles")
+            }
+            Self::Loop {
+                start,
+                period,
+                visited,
+            } => write!(
+                f,
+                "loops every {period} states from {} facing {:?}, visiting {visited} tiles",
+                start.position, start.direction
+            ),
         }
+    }
+}
 
-        if *grid.at(next_tile).unwrap() == Tile::Obstacle {
-            direction = direction.rotate();
-            continue;
+/// A fixed-size set of indices.
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(64)],
         }
+    }
+
+    /// Add `index` to the set, returning whether it was newly inserted.
+    fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        let new = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        new
+    }
+}
 
-        visited_positions.insert(guard_pos);
-        guard_pos = next_tile;
+/// The guard's next state: either a step forward or a turn to avoid an obstacle.
+/// Returns `None` if the guard walks off the map.
+fn advance(grid: &Grid<Tile>, rules: Rules, guard: Guard) -> Option<Guard> {
+    let mut next = guard.position + guard.direction.translation();
+    if rules.edges == Edges::Wrap {
+        next = grid.wrap(next);
     }
+    match grid.get(next)? {
+        Tile::Obstacle => Some(Guard {
+            direction: rules.turning.turn(guard.direction, guard.phase),
+            phase: (guard.phase + 1) % rules.turning.period(),
+            ..guard
+        }),
+        _ => Some(Guard {
+            position: next,
+            ..guard
+        }),
+    }
+}
 
-    visited_positions.len() + 1
+/// A unique index for each (position, direction, phase) state on the grid.
+fn state_index(grid: &Grid<Tile>, rules: Rules, guard: Guard) -> usize {
+    let cell = grid
+        .index_of(guard.position)
+        .expect("guard should be on the grid");
+    (cell * 4 + guard.direction as usize) * rules.turning.period() + guard.phase
 }
 
-fn escape(grid: &Grid<Tile>) -> bool {
-    let mut count = 0;
-    let mut direction = Direction::North;
-    let guard_pos = grid.data.iter().position(|t| *t == Tile::Guard).unwrap();
-    let mut guard_pos = (
-        (guard_pos % grid.width) as isize,
-        (guard_pos / grid.width) as isize,
+/// Walk the guard until it leaves the map or repeats a state.
+fn simulate(grid: &Grid<Tile>, rules: Rules, guard: Guard) -> Outcome {
+    walk(grid, rules, guard, |_| {})
+}
+
+/// Walk the guard until it leaves the map or repeats a state,
+/// calling `on_state` with every state reached (starting with `guard`).
+fn walk(
+    grid: &Grid<Tile>,
+    rules: Rules,
+    mut guard: Guard,
+    mut on_state: impl FnMut(Guard),
+) -> Outcome {
+    let cells = grid.as_slice().len();
+    let mut states = BitSet::new(cells * 4 * rules.turning.period());
+    let mut tiles = BitSet::new(cells);
+    tiles.insert(
+        grid.index_of(guard.position)
+            .expect("guard should be on the grid"),
     );
+    let (mut steps, mut visited) = (0, 1);
 
     loop {
-        let next_tile = (
-            guard_pos.0 + direction.translation().0,
-            guard_pos.1 + direction.translation().1,
-        );
-        if next_tile.0 < 0
-            || next_tile.1 < 0
-            || next_tile.0 as usize >= grid.width
-            || next_tile.1 as usize >= grid.data.len() / grid.width
-        {
-            return false;
+        if !states.insert(state_index(grid, rules, guard)) {
+            // The walk is deterministic, so the first repeated state is where the cycle begins.
+            return Outcome::Loop {
+                start: guard,
+                period: cycle_length(grid, rules, guard),
+                visited,
+            };
         }
-        // This value started at 1000, but the answer it gave
-        // was extremely incorrrect: some paths are very long.
-        if count >= 30_000 {
-            return true;
+        on_state(guard);
+
+        let Some(next) = advance(grid, rules, guard) else {
+            return Outcome::Exit { steps, visited };
+        };
+        if next.position != guard.position {
+            steps += 1;
+            if tiles.insert(
+                grid.index_of(next.position)
+                    .expect("guard should be on the grid"),
+            ) {
+                visited += 1;
+            }
         }
+        guard = next;
+    }
+}
 
-        if *grid.at(next_tile).unwrap() == Tile::Obstacle {
-            direction = direction.rotate();
-            continue;
+/// The number of states in the cycle containing `start`.
+fn cycle_length(grid: &Grid<Tile>, rules: Rules, start: Guard) -> usize {
+    let mut guard = start;
+    let mut period = 0;
+    loop {
+        guard = advance(grid, rules, guard).expect("a guard on a cycle can't leave the map");
+        period += 1;
+        if guard == start {
+            return period;
+        }
+    }
+}
+
+/// Every guard on the map, in reading order.
+fn find_guards(grid: &Grid<Tile>) -> Result<Vec<Guard>, Error> {
+    let guards: Vec<_> = grid
+        .enumerate()
+        .filter_map(|(position, tile)| match *tile {
+            Tile::Guard(direction) => Some(Guard {
+                position,
+                direction,
+                phase: 0,
+            }),
+            _ => None,
+        })
+        .collect();
+    if guards.is_empty() {
+        return Err(Error::NotFound("a guard"));
+    }
+    Ok(guards)
+}
+
+/// The result of simulating every guard on a map independently.
+/// Guards don't block each other, and their starting tiles are open floor.
+struct Patrol {
+    /// Each guard, and how its walk ended.
+    guards: Vec<(Guard, Outcome)>,
+    /// The tiles covered by at least one guard.
+    covered: Grid<bool>,
+}
+
+impl Patrol {
+    fn new(grid: &Grid<Tile>, rules: Rules, guards: &[Guard]) -> Self {
+        let mut covered = Grid::filled(grid.width(), grid.height(), false);
+        let guards = guards
+            .iter()
+            .map(|&guard| {
+                let outcome = walk(grid, rules, guard, |state| covered[state.position] = true);
+                (guard, outcome)
+            })
+            .collect();
+        Self { guards, covered }
+    }
+
+    /// The number of tiles covered by at least one guard.
+    fn covered_count(&self) -> usize {
+        self.covered.iter().filter(|&&covered| covered).count()
+    }
+}
+
+fn part1(input: &'static str) -> Result<usize, Error> {
+    let grid: Grid<Tile> = input.parse()?;
+    Ok(Patrol::new(&grid, Rules::PUZZLE, &find_guards(&grid)?).covered_count())
+}
+
+/// The positions of every obstacle, sorted by row and by column,
+/// so that a guard can jump straight to the next obstacle in front of it.
+struct ObstacleIndex {
+    width: usize,
+    /// The x coordinates of the obstacles in each row.
+    rows: Vec<Vec<isize>>,
+    /// The y coordinates of the obstacles in each column.
+    columns: Vec<Vec<isize>>,
+}
+
+impl ObstacleIndex {
+    fn new(grid: &Grid<Tile>) -> Self {
+        let mut rows = vec![vec![]; grid.height()];
+        let mut columns = vec![vec![]; grid.width()];
+        // Points are yielded row by row, so both lists come out sorted.
+        for (pt, tile) in grid.enumerate() {
+            if *tile == Tile::Obstacle {
+                rows[pt.y as usize].push(pt.x);
+                columns[pt.x as usize].push(pt.y);
+            }
+        }
+        Self {
+            width: grid.width(),
+            rows,
+            columns,
+        }
+    }
+
+    /// The tile where a guard stops before the next obstacle it's facing,
+    /// treating `extra` as an obstacle too. Returns `None` if the guard walks off the map.
+    fn next_stop(&self, guard: Guard, extra: Point) -> Option<Point> {
+        let Point { x, y } = guard.position;
+        match guard.direction {
+            Direction::North => {
+                let extra = (extra.x == x).then_some(extra.y);
+                before(&self.columns[x as usize], y, extra).map(|y| Point::new(x, y + 1))
+            }
+            Direction::South => {
+                let extra = (extra.x == x).then_some(extra.y);
+                after(&self.columns[x as usize], y, extra).map(|y| Point::new(x, y - 1))
+            }
+            Direction::West => {
+                let extra = (extra.y == y).then_some(extra.x);
+                before(&self.rows[y as usize], x, extra).map(|x| Point::new(x + 1, y))
+            }
+            Direction::East => {
+                let extra = (extra.y == y).then_some(extra.x);
+                after(&self.rows[y as usize], x, extra).map(|x| Point::new(x - 1, y))
+            }
         }
+    }
 
-        count += 1;
-        guard_pos = next_tile;
+    /// Whether the guard loops once `extra` is added as an obstacle.
+    /// Only the states where the guard turns are recorded in `seen`.
+    fn loops_with(&self, start: Guard, extra: Point, seen: &mut Seen) -> bool {
+        seen.clear();
+        let mut guard = start;
+        while let Some(stop) = self.next_stop(guard, extra) {
+            guard = Guard {
+                position: stop,
+                direction: guard.direction.rotate(),
+                ..guard
+            };
+            let cell = stop.y as usize * self.width + stop.x as usize;
+            if !seen.insert(cell * 4 + guard.direction as usize) {
+                return true;
+            }
+        }
+        false
     }
 }
 
-fn part2(input: &'static str) -> i32 {
-    let mut lines = input.lines().peekable();
-    let width = lines.peek().unwrap().len();
-    let mut grid: Grid<Tile> = Grid::new(width, vec![]);
+/// The nearest coordinate in `line` (or `extra`) less than `at`.
+fn before(line: &[isize], at: isize, extra: Option<isize>) -> Option<isize> {
+    let i = line.partition_point(|&c| c < at);
+    let found = i.checked_sub(1).map(|i| line[i]);
+    let extra = extra.filter(|&c| c < at);
+    found.max(extra)
+}
 
-    for line in lines {
-        for char in line.chars() {
-            grid.data.push(char.try_into().unwrap());
+/// The nearest coordinate in `line` (or `extra`) greater than `at`.
+fn after(line: &[isize], at: isize, extra: Option<isize>) -> Option<isize> {
+    let i = line.partition_point(|&c| c <= at);
+    let found = line.get(i).copied();
+    let extra = extra.filter(|&c| c > at);
+    match (found, extra) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// A set of states which can be cleared without touching its memory,
+/// for checking many candidate obstacles in a row.
+struct Seen {
+    stamps: Vec<u32>,
+    generation: u32,
+}
+
+impl Seen {
+    fn new(len: usize) -> Self {
+        Self {
+            stamps: vec![0; len],
+            generation: 0,
         }
     }
 
+    fn clear(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Add `index` to the set, returning whether it was newly inserted.
+    fn insert(&mut self, index: usize) -> bool {
+        let new = self.stamps[index] != self.generation;
+        self.stamps[index] = self.generation;
+        new
+    }
+}
+
+/// The guards which leave the map. Part 2 looks for obstacles which trap any of them;
+/// a guard which already loops is left out.
+fn escaping(grid: &Grid<Tile>, guards: &[Guard]) -> Vec<Guard> {
+    guards
+        .iter()
+        .copied()
+        .filter(|&guard| matches!(simulate(grid, Rules::PUZZLE, guard), Outcome::Exit { .. }))
+        .collect()
+}
+
+/// Every tile covered by one of the `escaping` guards, other than a guard's starting tile.
+/// A new obstacle anywhere else can't change their paths.
+fn candidates(grid: &Grid<Tile>, guards: &[Guard], escaping: &[Guard]) -> Vec<Point> {
+    let mut on_path = Patrol::new(grid, Rules::PUZZLE, escaping).covered;
+    for guard in guards {
+        on_path[guard.position] = false;
+    }
+
+    on_path
+        .enumerate()
+        .filter(|(_, &on_path)| on_path)
+        .map(|(pt, _)| pt)
+        .collect()
+}
+
+/// The number of worker threads to use, from [`THREADS_VAR`] or the available parallelism.
+fn thread_count() -> usize {
+    std::env::var(THREADS_VAR)
+        .ok()
+        .and_then(|threads| threads.parse().ok())
+        .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Find the candidates which make a guard loop, split between `threads` workers.
+/// The index is shared; each worker places its candidate obstacles as an overlay
+/// and keeps its own set of seen states.
+fn loop_obstacles(grid: &Grid<Tile>, guards: &[Guard], threads: usize) -> Vec<Point> {
+    let escaping = escaping(grid, guards);
+    let candidates = candidates(grid, guards, &escaping);
+    let index = ObstacleIndex::new(grid);
+    let states = grid.as_slice().len() * 4;
+
+    let threads = threads.clamp(1, candidates.len().max(1));
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|worker| {
+                let (index, candidates, escaping) = (&index, &candidates, &escaping);
+                s.spawn(move || {
+                    let mut seen = Seen::new(states);
+                    // Interleave the candidates, since nearby tiles tend to take similar time.
+                    candidates
+                        .iter()
+                        .skip(worker)
+                        .step_by(threads)
+                        .copied()
+                        .filter(|&pt| {
+                            escaping
+                                .iter()
+                                .any(|&guard| index.loops_with(guard, pt, &mut seen))
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut found: Vec<_> = workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("worker thread panicked"))
+            .collect();
+        found.sort_unstable_by_key(|pt| (pt.y, pt.x));
+        found
+    })
+}
+
+fn part2(input: &'static str) -> Result<usize, Error> {
+    let grid: Grid<Tile> = input.parse()?;
+    Ok(loop_obstacles(&grid, &find_guards(&grid)?, thread_count()).len())
+}
+
+fn part2_serial(input: &'static str) -> Result<usize, Error> {
+    let grid: Grid<Tile> = input.parse()?;
+    Ok(loop_obstacles(&grid, &find_guards(&grid)?, 1).len())
+}
+
+// The original implementation of part 2, which tries an obstacle on every empty tile
+// and walks the guard one step at a time. Compare the two with `--bench`.
+fn part2_naive(input: &'static str) -> Result<usize, Error> {
+    let mut grid: Grid<Tile> = input.parse()?;
+    let escaping = escaping(&grid, &find_guards(&grid)?);
+
     let mut matches = 0;
 
-    for x in 0..width {
-        for y in 0..(grid.data.len() / width) {
-            if *grid.at((x as isize, y as isize)).unwrap() == Tile::None {
-                grid.set((x as isize, y as isize), Tile::Obstacle);
-                if escape(&grid) {
-                    matches += 1;
-                }
-                grid.set((x as isize, y as isize), Tile::None);
+    for pt in grid.points() {
+        if grid[pt] == Tile::None {
+            grid.set(pt, Tile::Obstacle);
+            if escaping
+                .iter()
+                .any(|&guard| matches!(simulate(&grid, Rules::PUZZLE, guard), Outcome::Loop { .. }))
+            {
+                matches += 1;
+            }
+            grid.set(pt, Tile::None);
+        }
+    }
+
+    Ok(matches)
+}
+
+/// What is drawn on a tile by the renderer.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Glyph {
+    Empty,
+    Obstacle,
+    /// An obstacle which would make the guard loop, as found by part 2.
+    NewObstacle,
+    /// A tile the guard has walked through, vertically and/or horizontally.
+    Path {
+        vertical: bool,
+        horizontal: bool,
+    },
+    Guard(Direction),
+}
+
+impl Glyph {
+    /// The ANSI escape sequence used to colour this glyph in a terminal.
+    const fn ansi(self) -> &'static str {
+        match self {
+            Self::Empty => "\x1b[0;2m",
+            Self::Obstacle => "\x1b[0;1m",
+            Self::NewObstacle => "\x1b[0;1;33m",
+            Self::Path { .. } => "\x1b[0;36m",
+            Self::Guard(_) => "\x1b[0;1;31m",
+        }
+    }
+
+    /// The colour of this glyph in an image.
+    const fn rgb(self) -> [u8; 3] {
+        match self {
+            Self::Empty => [16, 16, 24],
+            Self::Obstacle => [160, 160, 160],
+            Self::NewObstacle => [255, 200, 0],
+            Self::Path { .. } => [40, 120, 200],
+            Self::Guard(_) => [230, 40, 40],
+        }
+    }
+}
+
+impl fmt::Display for Glyph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match *self {
+            Self::Empty => '.',
+            Self::Obstacle => '#',
+            Self::NewObstacle => 'O',
+            Self::Path {
+                vertical: true,
+                horizontal: true,
+            } => '+',
+            Self::Path { vertical: true, .. } => '|',
+            Self::Path { .. } => '-',
+            Self::Guard(direction) => match direction {
+                Direction::North => '^',
+                Direction::East => '>',
+                Direction::South => 'v',
+                Direction::West => '<',
+            },
+        };
+        write!(f, "{c}")
+    }
+}
+
+/// The map without its guard, ready to draw a path on.
+fn scene(grid: &Grid<Tile>) -> Grid<Glyph> {
+    grid.map(|tile| match tile {
+        Tile::Obstacle => Glyph::Obstacle,
+        _ => Glyph::Empty,
+    })
+}
+
+/// Draw the guard's path through the tile it's on, along the direction it's facing.
+/// A tile crossed in both directions (including a turn) is drawn as `+`.
+fn mark(scene: &mut Grid<Glyph>, guard: Guard) {
+    let along = matches!(guard.direction, Direction::North | Direction::South);
+    match &mut scene[guard.position] {
+        Glyph::Path {
+            vertical,
+            horizontal,
+        } => {
+            *vertical |= along;
+            *horizontal |= !along;
+        }
+        glyph => {
+            *glyph = Glyph::Path {
+                vertical: along,
+                horizontal: !along,
             }
         }
     }
+}
 
-    matches
+/// Draw every guard's whole path, with the guards at their starts, as the puzzle does.
+fn draw_paths(grid: &Grid<Tile>, rules: Rules, guards: &[Guard]) -> Grid<Glyph> {
+    let mut scene = scene(grid);
+    for &start in guards {
+        walk(grid, rules, start, |guard| mark(&mut scene, guard));
+    }
+    for start in guards {
+        scene[start.position] = Glyph::Guard(start.direction);
+    }
+    scene
+}
+
+fn write_ansi(out: &mut impl Write, scene: &Grid<Glyph>) -> io::Result<()> {
+    for row in scene.rows() {
+        for glyph in row {
+            write!(out, "{}{glyph}", glyph.ansi())?;
+        }
+        writeln!(out, "\x1b[0m")?;
+    }
+    Ok(())
+}
+
+/// Write a scene as a binary PPM image, with each tile drawn as a `scale`-pixel square.
+fn write_ppm(path: &Path, scene: &Grid<Glyph>, scale: usize) -> io::Result<()> {
+    let (width, height) = (scene.width() * scale, scene.height() * scale);
+    let mut image = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for row in scene.rows() {
+        let line: Vec<u8> = row
+            .iter()
+            .flat_map(|glyph| std::iter::repeat_n(glyph.rgb(), scale).flatten())
+            .collect();
+        for _ in 0..scale {
+            image.extend_from_slice(&line);
+        }
+    }
+    std::fs::write(path, image)
+}
+
+/// Print a finished scene, or write it as an image with `ppm FILE`.
+fn show(scene: &Grid<Glyph>, args: &[String]) -> ViewResult {
+    match args {
+        [] => print!("{scene}"),
+        [format, file] if format == "ppm" => write_ppm(Path::new(file), scene, PPM_SCALE)?,
+        _ => return Err("expected no arguments, or `ppm FILE`".into()),
+    }
+    Ok(())
+}
+
+/// The size, in pixels, of each tile in an image.
+const PPM_SCALE: usize = 4;
+
+/// View: each guard's path, drawn with `|`, `-` and `+`, and how far each guard walked.
+/// Takes `ppm FILE` to write an image instead of printing the map,
+/// and `--turn TURNS` or `--wrap` to change the rules (see [`RuleArgs`]).
+fn path(input: &'static str, args: &[String]) -> ViewResult {
+    let args = RuleArgs::parse(args)?;
+    let grid: Grid<Tile> = input.parse()?;
+    let guards = find_guards(&grid)?;
+    show(&draw_paths(&grid, args.rules(), &guards), &args.rest)?;
+
+    let patrol = Patrol::new(&grid, args.rules(), &guards);
+    for (i, (guard, outcome)) in patrol.guards.iter().enumerate() {
+        eprintln!(
+            "guard {} at {} facing {:?} {outcome}",
+            i + 1,
+            guard.position,
+            guard.direction
+        );
+    }
+    eprintln!("{} tiles covered in total", patrol.covered_count());
+    Ok(())
+}
+
+/// View: the guards' original paths, with every obstacle which would make one loop drawn as `O`.
+/// Takes `ppm FILE` to write an image instead of printing the map.
+fn loops(input: &'static str, args: &[String]) -> ViewResult {
+    let grid: Grid<Tile> = input.parse()?;
+    let guards = find_guards(&grid)?;
+    let mut scene = draw_paths(&grid, Rules::PUZZLE, &guards);
+    let found = loop_obstacles(&grid, &guards, thread_count());
+    for &pt in &found {
+        scene[pt] = Glyph::NewObstacle;
+    }
+    show(&scene, args)?;
+    eprintln!("{} obstacles make a guard loop", found.len());
+    Ok(())
+}
+
+/// View: a frame-by-frame animation of the guards' walks, with every guard moving at once.
+///
+/// `animate [ansi] [--delay MS] [--every N]` replays the walk in the terminal (redirect it
+/// to a file and `cat` it to replay it later), and `animate ppm DIR [--every N]` writes
+/// each frame to `DIR/frameNNNNN.ppm`. Only every `N`th state is drawn.
+/// Takes `--turn TURNS` or `--wrap` to change the rules (see [`RuleArgs`]).
+fn animate(input: &'static str, args: &[String]) -> ViewResult {
+    let rule_args = RuleArgs::parse(args)?;
+    let mut dir = None;
+    let mut delay = Duration::from_millis(30);
+    let mut every = 1;
+
+    let mut args = rule_args.rest.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or_else(|| format!("expected a number after `{flag}`"))
+        };
+        match arg.as_str() {
+            "ansi" => dir = None,
+            "ppm" => dir = Some(args.next().ok_or("expected a directory after `ppm`")?),
+            "--delay" => delay = Duration::from_millis(value("--delay")?),
+            "--every" => every = value("--every")?.max(1) as usize,
+            other => return Err(format!("unexpected argument `{other}`").into()),
+        }
+    }
+
+    let grid: Grid<Tile> = input.parse()?;
+    let walks: Vec<_> = find_guards(&grid)?
+        .into_iter()
+        .map(|start| {
+            let mut states = vec![];
+            walk(&grid, rule_args.rules(), start, |guard| states.push(guard));
+            states
+        })
+        .collect();
+
+    if let Some(dir) = dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    if dir.is_none() {
+        // Clear the screen and hide the cursor.
+        write!(out, "\x1b[2J\x1b[?25l")?;
+    }
+
+    let mut scene = scene(&grid);
+    let last = walks.iter().map(Vec::len).max().unwrap_or(0) - 1;
+    let mut frame = 0;
+    for i in 0..=last {
+        // Guards which have already finished their walk are no longer drawn.
+        let guards: Vec<_> = walks.iter().filter_map(|states| states.get(i)).collect();
+        for &&guard in &guards {
+            mark(&mut scene, guard);
+        }
+        if i % every != 0 && i != last {
+            continue;
+        }
+        frame += 1;
+
+        let mut drawn = scene.clone();
+        for guard in guards {
+            drawn[guard.position] = Glyph::Guard(guard.direction);
+        }
+        if let Some(dir) = dir {
+            let file = Path::new(dir).join(format!("frame{frame:05}.ppm"));
+            write_ppm(&file, &drawn, PPM_SCALE)?;
+        } else {
+            write!(out, "\x1b[H")?;
+            write_ansi(&mut out, &drawn)?;
+            out.flush()?;
+            std::thread::sleep(delay);
+        }
+    }
+
+    if dir.is_none() {
+        write!(out, "\x1b[?25h")?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str = "....#.....
+    const SAMPLE_INPUT: &str = "....#.....
 .........#
 ..........
 ..#.......
@@ -212,6 +922,203 @@ mod test {
 #.........
 ......#...";
 
-    test_day!(test_part1 -> part1(SAMPLE_INPUT), 41);
-    test_day!(test_part2 -> part2(SAMPLE_INPUT), 6);
+    test_day!(test_part1 -> part1(SAMPLE_INPUT), Ok(41));
+    test_day!(test_part2 -> part2(SAMPLE_INPUT), Ok(6));
+    test_day!(test_part2_serial -> part2_serial(SAMPLE_INPUT), Ok(6));
+    test_day!(test_part2_naive -> part2_naive(SAMPLE_INPUT), Ok(6));
+    test_day!(test_real_input -> DAY);
+
+    // Obstacles can trap two of the first guard's paths and one of the second's.
+    const TWO_GUARDS: &str = "..#...
+..^...
+.#....
+#.<##.
+.#...#
+.#....";
+
+    // One guard which loops and one which walks straight off the map.
+    const GUARDS_INPUT: &str = ".#..
+...#
+#^..
+..#.
+>...";
+
+    test_day!(test_part1_guards -> part1(GUARDS_INPUT), Ok(8));
+    test_day!(test_part2_guards -> part2, [(GUARDS_INPUT, Ok(0)), (TWO_GUARDS, Ok(3))]);
+    test_day!(test_part2_naive_guards -> part2_naive(TWO_GUARDS), Ok(3));
+
+    #[test]
+    fn test_thread_counts() {
+        let grid: Grid<Tile> = SAMPLE_INPUT.parse().unwrap();
+        let guards = find_guards(&grid).unwrap();
+        for threads in [0, 1, 2, 3, 64] {
+            let found = loop_obstacles(&grid, &guards, threads);
+            assert_eq!(found.len(), 6);
+            assert_eq!(found[0], Point::new(3, 6));
+        }
+    }
+
+    #[test]
+    fn test_patrol() {
+        let grid: Grid<Tile> = GUARDS_INPUT.parse().unwrap();
+        let guards = find_guards(&grid).unwrap();
+        assert_eq!(
+            guards.iter().map(|g| g.direction).collect::<Vec<_>>(),
+            [Direction::North, Direction::East]
+        );
+
+        let patrol = Patrol::new(&grid, Rules::PUZZLE, &guards);
+        assert!(matches!(
+            patrol.guards[0].1,
+            Outcome::Loop { visited: 4, .. }
+        ));
+        assert_eq!(
+            patrol.guards[1].1,
+            Outcome::Exit {
+                steps: 3,
+                visited: 4
+            }
+        );
+        assert_eq!(patrol.covered_count(), 8);
+    }
+
+    #[test]
+    fn test_outcome() {
+        let grid: Grid<Tile> = SAMPLE_INPUT.parse().unwrap();
+        let guard = find_guards(&grid).unwrap()[0];
+        assert!(matches!(
+            simulate(&grid, Rules::PUZZLE, guard),
+            Outcome::Exit { visited: 41, .. }
+        ));
+
+        let grid: Grid<Tile> = ".#..\n...#\n#^..\n..#.".parse().unwrap();
+        let guard = find_guards(&grid).unwrap()[0];
+        assert_eq!(
+            simulate(&grid, Rules::PUZZLE, guard),
+            Outcome::Loop {
+                start: guard,
+                period: 8,
+                visited: 4
+            }
+        );
+    }
+
+    #[test]
+    fn test_turn_strategies() {
+        let grid: Grid<Tile> = SAMPLE_INPUT.parse().unwrap();
+        let guard = find_guards(&grid).unwrap()[0];
+        let run =
+            |turning: &dyn TurnStrategy, edges| simulate(&grid, Rules { turning, edges }, guard);
+
+        assert_eq!(
+            run(&Turn::Left, Edges::Exit),
+            Outcome::Exit {
+                steps: 9,
+                visited: 10
+            }
+        );
+        assert_eq!(
+            run(&Turn::Reverse, Edges::Exit),
+            Outcome::Exit {
+                steps: 13,
+                visited: 9
+            }
+        );
+        // With nowhere to leave, every walk ends in a loop.
+        for turning in [
+            &Turn::Right as &dyn TurnStrategy,
+            &Turn::Left,
+            &Turn::Reverse,
+        ] {
+            assert!(matches!(run(turning, Edges::Wrap), Outcome::Loop { .. }));
+        }
+
+        let grid: Grid<Tile> = ".\n^\n.".parse().unwrap();
+        let guard = find_guards(&grid).unwrap()[0];
+        let rules = Rules {
+            edges: Edges::Wrap,
+            ..Rules::PUZZLE
+        };
+        assert_eq!(
+            simulate(&grid, rules, guard),
+            Outcome::Loop {
+                start: guard,
+                period: 3,
+                visited: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_pattern_phase() {
+        // A boxed-in guard only turns. Alternating right and reverse passes through each
+        // direction twice, in different phases, before the walk repeats.
+        let grid: Grid<Tile> = "###\n#^#\n###".parse().unwrap();
+        let guard = find_guards(&grid).unwrap()[0];
+        let pattern = Pattern::new(vec![Turn::Right, Turn::Reverse]).unwrap();
+        let rules = Rules {
+            turning: &pattern,
+            ..Rules::PUZZLE
+        };
+        assert_eq!(
+            simulate(&grid, rules, guard),
+            Outcome::Loop {
+                start: guard,
+                period: 8,
+                visited: 1
+            }
+        );
+        assert_eq!(
+            simulate(&grid, Rules::PUZZLE, guard),
+            Outcome::Loop {
+                start: guard,
+                period: 4,
+                visited: 1
+            }
+        );
+        assert_eq!(Pattern::new(vec![]), None);
+    }
+
+    #[test]
+    fn test_draw_path() {
+        let grid: Grid<Tile> = SAMPLE_INPUT.parse().unwrap();
+        let scene = draw_paths(&grid, Rules::PUZZLE, &find_guards(&grid).unwrap());
+        assert_eq!(
+            scene.to_string(),
+            "....#.....
+....+---+#
+....|...|.
+..#.|...|.
+..+-+-+#|.
+..|.|.|.|.
+.#+-^-+-+.
+.+----++#.
+#+----+|..
+......#|..
+"
+        );
+    }
+
+    #[test]
+    fn test_next_stop() {
+        let grid: Grid<Tile> = SAMPLE_INPUT.parse().unwrap();
+        let index = ObstacleIndex::new(&grid);
+        let guard = find_guards(&grid).unwrap()[0];
+        let far = Point::new(100, 100);
+
+        assert_eq!(index.next_stop(guard, far), Some(Point::new(4, 1)));
+        assert_eq!(
+            index.next_stop(guard, Point::new(4, 3)),
+            Some(Point::new(4, 4))
+        );
+        let east = Guard {
+            direction: Direction::East,
+            ..guard
+        };
+        assert_eq!(index.next_stop(east, far), None);
+        assert_eq!(
+            index.next_stop(east, Point::new(7, 6)),
+            Some(Point::new(6, 6))
+        );
+    }
 }
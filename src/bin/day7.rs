@@ -1,10 +1,69 @@
-use std::iter::repeat_n;
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
 
+use aoc_2024::error::Line;
 use aoc_2024::*;
-use itertools::Itertools;
 
 day!(part1, part2);
 
+/// The unsigned integer types equations can be solved in.
+trait Number: Copy + Eq + Into<u128> + FromStr<Err = ParseIntError> {
+    const ZERO: Self;
+    const TEN: Self;
+
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exp: u32) -> Option<Self>;
+    /// The number of decimal digits in `self`.
+    fn digits(self) -> u32;
+}
+
+macro_rules! impl_number {
+    ($($ty:ty),+) => {
+        $(impl Number for $ty {
+            const ZERO: Self = 0;
+            const TEN: Self = 10;
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                self.checked_add(rhs)
+            }
+            fn checked_sub(self, rhs: Self) -> Option<Self> {
+                self.checked_sub(rhs)
+            }
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                self.checked_mul(rhs)
+            }
+            fn checked_div(self, rhs: Self) -> Option<Self> {
+                self.checked_div(rhs)
+            }
+            fn checked_rem(self, rhs: Self) -> Option<Self> {
+                self.checked_rem(rhs)
+            }
+            fn checked_pow(self, exp: u32) -> Option<Self> {
+                self.checked_pow(exp)
+            }
+            fn digits(self) -> u32 {
+                self.checked_ilog10().unwrap_or(0) + 1
+            }
+        })+
+    };
+}
+
+impl_number!(u64, u128);
+
+/// Parse a line of the form `result: value value ...`.
+fn parse_line<N: Number>(line: Line) -> Result<(N, Vec<N>), Error> {
+    let (result, values) = line.split_once(": ")?;
+    let values = values
+        .split(' ')
+        .map(|d| line.parse(d))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((line.parse(result)?, values))
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Operator {
     Add,
@@ -12,92 +71,167 @@ enum Operator {
     Concat,
 }
 
-fn part1(input: &'static str) -> u64 {
-    let lines = input.lines();
-    let mut total = 0;
-    for line in lines {
-        let (result, values) = line.split_once(": ").unwrap();
-        let result: u64 = result.parse().unwrap();
-        let values: Vec<u64> = values.split(' ').map(|d| d.parse().unwrap()).collect();
-
-        let op_len = values.len() - 1;
-        println!("Running {} -> {:?} n={}", result, values, op_len);
-
-        // let ops =
-        //     .into_iter()
-        //     .permutations(op_len);
-
-        let ops = repeat_n([Operator::Add, Operator::Multiply].into_iter(), op_len)
-            .multi_cartesian_product();
-
-        for operation_set in ops {
-            let mut operation_set = operation_set.iter();
-            let mut values = values.iter();
-
-            let mut acc = *values.next().unwrap();
-            for value in values {
-                match operation_set.next().unwrap() {
-                    Operator::Add => acc = acc.saturating_add(*value),
-                    Operator::Multiply => acc = acc.saturating_mul(*value),
-                    Operator::Concat => unreachable!("Not considererd in `ops`"),
-                }
-            }
+impl Operator {
+    /// Apply the operator to the accumulated value (operators are evaluated left to right).
+    /// Returns `None` if the result overflows.
+    pub fn apply<N: Number>(self, acc: N, value: N) -> Option<N> {
+        match self {
+            Self::Add => acc.checked_add(value),
+            Self::Multiply => acc.checked_mul(value),
+            Self::Concat => acc
+                .checked_mul(N::TEN.checked_pow(value.digits())?)?
+                .checked_add(value),
+        }
+    }
 
-            if acc == result {
-                total += result;
-                break;
+    /// Find the accumulated value `acc` for which `acc <op> value == target`, if there is one.
+    /// This is what lets the solver work right to left, pruning impossible branches early.
+    ///
+    /// Multiplying by zero is handled by the solver, since any `acc` would do.
+    pub fn unapply<N: Number>(self, target: N, value: N) -> Option<N> {
+        match self {
+            Self::Add => target.checked_sub(value),
+            Self::Multiply => {
+                (target.checked_rem(value)? == N::ZERO).then(|| target.checked_div(value))?
+            }
+            Self::Concat => {
+                let shift = N::TEN.checked_pow(value.digits())?;
+                (target.checked_rem(shift)? == value).then(|| target.checked_div(shift))?
             }
         }
     }
-    total
 }
 
-fn part2(input: &'static str) -> u64 {
-    let lines = input.lines();
-    let mut total = 0;
-    for line in lines {
-        let (result, values) = line.split_once(": ").unwrap();
-        let result: u64 = result.parse().unwrap();
-        let values: Vec<u64> = values.split(' ').map(|d| d.parse().unwrap()).collect();
-
-        let op_len = values.len() - 1;
-
-        let ops = repeat_n(
-            [Operator::Add, Operator::Multiply, Operator::Concat].into_iter(),
-            op_len,
-        )
-        .multi_cartesian_product();
-
-        for operation_set in ops {
-            let mut operation_set = operation_set.iter();
-            let mut values = values.iter();
-            let mut acc = *values.next().unwrap();
-            for value in values {
-                match operation_set.next().unwrap() {
-                    // FIXME: Saturation is technically not *correct*.
-                    Operator::Add => acc = acc.saturating_add(*value),
-                    Operator::Multiply => acc = acc.saturating_mul(*value),
-                    Operator::Concat => {
-                        let val = acc.to_string() + &((*value).to_string());
-                        acc = val.parse().unwrap_or(u64::MAX);
-                    }
+/// Find a sequence of operators, chosen from `operators`, which makes `values` evaluate to `target`.
+/// The returned sequence has one operator between each pair of values.
+fn solve<N: Number>(target: N, values: &[N], operators: &[Operator]) -> Option<Vec<Operator>> {
+    let mut chosen = Vec::with_capacity(values.len().saturating_sub(1));
+    if !search(target, values, operators, &mut chosen) {
+        return None;
+    }
+
+    chosen.reverse();
+    debug_assert!(evaluate(values, &chosen) == Some(target));
+    Some(chosen)
+}
+
+/// Recursively search for operators from the last value backwards, pushing them onto `chosen`.
+fn search<N: Number>(
+    target: N,
+    values: &[N],
+    operators: &[Operator],
+    chosen: &mut Vec<Operator>,
+) -> bool {
+    let (&last, rest) = match values.split_last() {
+        None => return false,
+        Some((&first, [])) => return first == target,
+        Some(split) => split,
+    };
+
+    for &op in operators {
+        if op == Operator::Multiply && last == N::ZERO {
+            // Anything multiplied by zero is zero, so the operators before this one don't matter,
+            // as long as evaluating them doesn't overflow.
+            if target == N::ZERO {
+                let mut prefix = Vec::with_capacity(rest.len() - 1);
+                if any_evaluation(rest[0], &rest[1..], operators, &mut prefix) {
+                    prefix.reverse();
+                    chosen.push(op);
+                    chosen.extend(prefix);
+                    return true;
                 }
             }
+            continue;
+        }
 
-            if acc == result {
-                total += result;
-                break;
-            }
+        let Some(acc) = op.unapply(target, last) else {
+            continue;
+        };
+        chosen.push(op);
+        if search(acc, rest, operators, chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+    false
+}
+
+/// Search forwards for any operators which evaluate `values` (starting from `acc`) without
+/// overflowing, pushing them onto `chosen`. Only overflowing branches are pruned.
+fn any_evaluation<N: Number>(
+    acc: N,
+    values: &[N],
+    operators: &[Operator],
+    chosen: &mut Vec<Operator>,
+) -> bool {
+    let Some((&value, rest)) = values.split_first() else {
+        return true;
+    };
+    for &op in operators {
+        let Some(next) = op.apply(acc, value) else {
+            continue;
+        };
+        chosen.push(op);
+        if any_evaluation(next, rest, operators, chosen) {
+            return true;
+        }
+        chosen.pop();
+    }
+    false
+}
+
+/// Evaluate `values` left to right with the given operators between them.
+/// Returns `None` if any step overflows.
+fn evaluate<N: Number>(values: &[N], operators: &[Operator]) -> Option<N> {
+    values[1..]
+        .iter()
+        .zip(operators)
+        .try_fold(values[0], |acc, (value, op)| op.apply(acc, *value))
+}
+
+/// Sum the targets of every equation which can be solved using `operators`, working in `N`.
+fn total_calibration<N: Number>(
+    input: &'static str,
+    operators: &[Operator],
+) -> Result<u128, Error> {
+    let mut total = 0;
+    for line in error::lines(input) {
+        let (result, values) = parse_line::<N>(line)?;
+        if solve(result, &values, operators).is_some() {
+            total += result.into();
         }
     }
-    total
+    Ok(total)
+}
+
+/// Sum the targets of every equation which can be solved using `operators`.
+/// Equations are solved with `u64`s, unless the input contains a number which doesn't fit in one,
+/// in which case the whole input is solved with `u128`s instead.
+fn calibrate(input: &'static str, operators: &[Operator]) -> Result<u128, Error> {
+    match total_calibration::<u64>(input, operators) {
+        Err(Error::InvalidNumber { source, .. }) if *source.kind() == IntErrorKind::PosOverflow => {
+            total_calibration::<u128>(input, operators)
+        }
+        result => result,
+    }
+}
+
+fn part1(input: &'static str) -> Result<u128, Error> {
+    calibrate(input, &[Operator::Add, Operator::Multiply])
+}
+
+fn part2(input: &'static str) -> Result<u128, Error> {
+    calibrate(
+        input,
+        &[Operator::Add, Operator::Multiply, Operator::Concat],
+    )
 }
 
 #[cfg(test)]
 mod test {
-    use crate::*;
+    use super::*;
 
-    const SAMPLE_INPUT: &'static str = "190: 10 19
+    const SAMPLE_INPUT: &str = "190: 10 19
 3267: 81 40 27
 83: 17 5
 156: 15 6
@@ -107,6 +241,53 @@ mod test {
 21037: 9 7 18 13
 292: 11 6 16 20";
 
-    test_day!(test_part1 -> part1(SAMPLE_INPUT), 3749);
-    test_day!(test_part2 -> part2(SAMPLE_INPUT), 11387);
+    test_day!(test_part1 -> part1(SAMPLE_INPUT), Ok(3749));
+    test_day!(test_part2 -> part2(SAMPLE_INPUT), Ok(11387));
+    test_day!(test_real_input -> DAY);
+
+    #[test]
+    fn test_solve_returns_operators() {
+        use Operator::*;
+        let all = [Add, Multiply, Concat];
+
+        assert_eq!(
+            solve(3267u64, &[81, 40, 27], &all),
+            Some(vec![Multiply, Add])
+        );
+        assert_eq!(
+            solve(7290u64, &[6, 8, 6, 15], &all),
+            Some(vec![Multiply, Concat, Multiply])
+        );
+        assert_eq!(solve(7290u64, &[6, 8, 6, 15], &[Add, Multiply]), None);
+        assert_eq!(
+            solve(0u64, &[5, 3, 0], &[Add, Multiply]),
+            Some(vec![Add, Multiply])
+        );
+        assert_eq!(solve(50u64, &[5, 0], &all), Some(vec![Concat]));
+        assert_eq!(solve(0, &[u64::MAX, 2, 0], &[Multiply]), None);
+        assert_eq!(solve(0, &[u64::MAX, 2, 0], &all), None);
+        // The first operator overflows, but another choice before the zero doesn't.
+        assert_eq!(
+            solve(0u64, &[u64::MAX, 1, 0], &[Add, Multiply]),
+            Some(vec![Multiply, Multiply])
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        use Operator::*;
+        assert_eq!(Concat.apply(u64::MAX / 10, 6), None);
+        assert_eq!(Concat.apply(12u64, 345), Some(12345));
+        assert_eq!(Concat.unapply(12345u64, 345), Some(12));
+        assert_eq!(Concat.unapply(5u64, u64::MAX), None);
+        assert_eq!(
+            solve(u64::MAX, &[u64::MAX, 1, 1], &[Add, Multiply, Concat]),
+            Some(vec![Multiply, Multiply])
+        );
+    }
+
+    test_day!(test_wide -> part1, [
+        ("18446744073709551616: 18446744073709551615 1", Ok(1 << 64)),
+        ("18446744073709551615: 18446744073709551615 2", Ok(0)),
+    ]);
 }
@@ -0,0 +1,220 @@
+//! The error type shared by every day's parsers and parts.
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+use crate::grid::ParseGridError;
+
+/// An error encountered while parsing an input or solving a part.
+/// Line and column numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The input (or a required section of it) was empty.
+    EmptyInput,
+    /// A number could not be parsed.
+    InvalidNumber {
+        line: usize,
+        column: usize,
+        found: String,
+        source: ParseIntError,
+    },
+    /// A character was not valid at this position.
+    UnexpectedChar {
+        line: usize,
+        column: usize,
+        found: char,
+    },
+    /// A line ended before an expected field or delimiter.
+    MissingField {
+        line: usize,
+        column: usize,
+        expected: &'static str,
+    },
+    /// A row of a grid had a different width to the first row.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The input was well-formed, but didn't contain something the puzzle requires.
+    NotFound(&'static str),
+    /// The input was well-formed, but contradicts itself.
+    Contradiction { line: usize, reason: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyInput => write!(f, "input is empty"),
+            Self::InvalidNumber {
+                line,
+                column,
+                found,
+                source,
+            } => write!(f, "{line}:{column}: invalid number {found:?}: {source}"),
+            Self::UnexpectedChar {
+                line,
+                column,
+                found,
+            } => write!(f, "{line}:{column}: unexpected character {found:?}"),
+            Self::MissingField {
+                line,
+                column,
+                expected,
+            } => write!(f, "{line}:{column}: expected {expected}"),
+            Self::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected a row of width {expected}, found {found}"
+            ),
+            Self::NotFound(what) => write!(f, "input does not contain {what}"),
+            Self::Contradiction { line, reason } => write!(f, "line {line}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidNumber { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<ParseGridError<E>> for Error {
+    fn from(e: ParseGridError<E>) -> Self {
+        match e {
+            ParseGridError::Empty => Self::EmptyInput,
+            ParseGridError::Ragged {
+                line,
+                expected,
+                found,
+            } => Self::RaggedRow {
+                line,
+                expected,
+                found,
+            },
+            ParseGridError::InvalidTile {
+                line,
+                column,
+                found,
+                ..
+            } => Self::UnexpectedChar {
+                line,
+                column,
+                found,
+            },
+        }
+    }
+}
+
+/// A line of input, which knows its position so that errors can point at it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Line<'a> {
+    /// The 1-based line number.
+    pub number: usize,
+    pub text: &'a str,
+}
+
+/// Iterate over the lines of an input, keeping track of line numbers.
+pub fn lines(input: &str) -> impl Iterator<Item = Line<'_>> {
+    input.lines().enumerate().map(|(i, text)| Line {
+        number: i + 1,
+        text,
+    })
+}
+
+impl<'a> Line<'a> {
+    /// The 1-based column at which `field` starts.
+    /// `field` should be a slice of this line; otherwise the end of the line is used.
+    pub fn column_of(&self, field: &str) -> usize {
+        let offset = (field.as_ptr() as usize).wrapping_sub(self.text.as_ptr() as usize);
+        offset.min(self.text.len()) + 1
+    }
+
+    /// Parse a number from `field`, which should be a slice of this line.
+    pub fn parse<T>(&self, field: &str) -> Result<T, Error>
+    where
+        T: FromStr<Err = ParseIntError>,
+    {
+        field.parse().map_err(|source| Error::InvalidNumber {
+            line: self.number,
+            column: self.column_of(field),
+            found: field.to_string(),
+            source,
+        })
+    }
+
+    /// Split this line at the first occurrence of `delimiter`.
+    pub fn split_once(&self, delimiter: &'static str) -> Result<(&'a str, &'a str), Error> {
+        self.text
+            .split_once(delimiter)
+            .ok_or_else(|| self.missing(delimiter))
+    }
+
+    /// An error for a field which should have appeared at the end of this line.
+    pub fn missing(&self, expected: &'static str) -> Error {
+        Error::MissingField {
+            line: self.number,
+            column: self.text.len() + 1,
+            expected,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn errors_point_at_fields() {
+        let input = "1 2\n3 x4";
+        let line = lines(input).nth(1).unwrap();
+        let field = line.text.split(' ').nth(1).unwrap();
+
+        assert_eq!(
+            line.parse::<u32>(line.text.split(' ').next().unwrap()),
+            Ok(3)
+        );
+        let e = line.parse::<u32>(field).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::InvalidNumber {
+                line: 2,
+                column: 3,
+                ..
+            }
+        ));
+        assert_eq!(
+            e.to_string(),
+            "2:3: invalid number \"x4\": invalid digit found in string"
+        );
+
+        assert_eq!(
+            line.split_once(": "),
+            Err(Error::MissingField {
+                line: 2,
+                column: 5,
+                expected: ": "
+            })
+        );
+    }
+
+    #[test]
+    fn from_grid_error() {
+        let e = crate::grid::Grid::<u8>::parse("1\n€").unwrap_err();
+        assert_eq!(
+            Error::from(e),
+            Error::UnexpectedChar {
+                line: 2,
+                column: 1,
+                found: '€'
+            }
+        );
+    }
+}
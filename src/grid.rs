@@ -0,0 +1,496 @@
+//! A rectangular grid of tiles, as used by most of the map-based puzzles.
+//!
+//! Points are always signed ([`Point`]), so that translations which step off the edge of the
+//! grid can be represented; every accessor checks bounds and returns `None` instead of panicking.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+/// A signed position (or translation) on a [`Grid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+/// The four orthogonal translations, clockwise from north.
+pub const ORTHOGONAL_TRANSLATIONS: [Point; 4] = [
+    Point::new(0, -1),
+    Point::new(1, 0),
+    Point::new(0, 1),
+    Point::new(-1, 0),
+];
+
+/// All eight translations to a neighbouring point, including diagonals.
+pub const ALL_TRANSLATIONS: [Point; 8] = [
+    Point::new(-1, -1),
+    Point::new(-1, 0),
+    Point::new(-1, 1),
+    Point::new(0, -1),
+    Point::new(0, 1),
+    Point::new(1, -1),
+    Point::new(1, 0),
+    Point::new(1, 1),
+];
+
+impl Point {
+    pub const fn new(x: isize, y: isize) -> Self {
+        Self { x, y }
+    }
+
+    /// Create a point from unsigned coordinates.
+    /// Returns `None` if either coordinate does not fit in an `isize`.
+    pub fn from_unsigned(x: usize, y: usize) -> Option<Self> {
+        Some(Self::new(x.try_into().ok()?, y.try_into().ok()?))
+    }
+
+    /// Convert this point to unsigned coordinates.
+    /// Returns `None` if either coordinate is negative.
+    pub fn to_unsigned(self) -> Option<(usize, usize)> {
+        Some((self.x.try_into().ok()?, self.y.try_into().ok()?))
+    }
+
+    /// Add two points, returning `None` on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        Some(Self::new(
+            self.x.checked_add(other.x)?,
+            self.y.checked_add(other.y)?,
+        ))
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl TryFrom<(usize, usize)> for Point {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from((x, y): (usize, usize)) -> Result<Self, Self::Error> {
+        Ok(Self::new(x.try_into()?, y.try_into()?))
+    }
+}
+
+impl TryFrom<Point> for (usize, usize) {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(pt: Point) -> Result<Self, Self::Error> {
+        Ok((pt.x.try_into()?, pt.y.try_into()?))
+    }
+}
+
+impl Add for Point {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl SubAssign for Point {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Point {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<isize> for Point {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+/// An error encountered while parsing a [`Grid`] from text.
+/// Line and column numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseGridError<E> {
+    /// The input contained no rows.
+    Empty,
+    /// A row had a different number of tiles to the first row.
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A character could not be converted into a tile.
+    InvalidTile {
+        line: usize,
+        column: usize,
+        found: char,
+        error: E,
+    },
+}
+
+impl<E: fmt::Debug> fmt::Display for ParseGridError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "grid is empty"),
+            Self::Ragged {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: expected a row of width {expected}, found {found}"
+            ),
+            Self::InvalidTile {
+                line,
+                column,
+                found,
+                error,
+            } => write!(f, "{line}:{column}: invalid tile {found:?} ({error:?})"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for ParseGridError<E> {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    items: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Create a grid from row-major data.
+    ///
+    /// ## Panics
+    /// Panics if `items.len()` is not a multiple of `width`.
+    pub fn from_vec(width: usize, items: Vec<T>) -> Self {
+        assert!(
+            width > 0 && items.len().is_multiple_of(width),
+            "Grid data of length {} does not fit width {}",
+            items.len(),
+            width
+        );
+        Self {
+            width,
+            height: items.len() / width,
+            items,
+        }
+    }
+
+    /// Create a grid of a given size, with every tile set to `value`.
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_vec(width, vec![value; width * height])
+    }
+
+    /// Parse a grid from newline-separated rows, converting each character with `T::try_from`.
+    /// Since every `From<char>` type is also `TryFrom<char>`, this works for both.
+    pub fn parse(input: &str) -> Result<Self, ParseGridError<T::Error>>
+    where
+        T: TryFrom<char>,
+    {
+        let mut width = None;
+        let mut items = vec![];
+
+        for (line_no, line) in input.lines().enumerate() {
+            let start = items.len();
+            for (col_no, ch) in line.chars().enumerate() {
+                let tile = T::try_from(ch).map_err(|error| ParseGridError::InvalidTile {
+                    line: line_no + 1,
+                    column: col_no + 1,
+                    found: ch,
+                    error,
+                })?;
+                items.push(tile);
+            }
+
+            let row_width = items.len() - start;
+            match width {
+                None => width = Some(row_width),
+                Some(expected) if expected != row_width => {
+                    return Err(ParseGridError::Ragged {
+                        line: line_no + 1,
+                        expected,
+                        found: row_width,
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        match width {
+            None | Some(0) => Err(ParseGridError::Empty),
+            Some(width) => Ok(Self::from_vec(width, items)),
+        }
+    }
+
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether the given point lies within the grid.
+    pub const fn contains(&self, pt: Point) -> bool {
+        pt.x >= 0 && pt.y >= 0 && (pt.x as usize) < self.width && (pt.y as usize) < self.height
+    }
+
+    /// Get the index into the underlying data of a point.
+    /// If it is outside the grid, `None` will be returned.
+    pub const fn index_of(&self, pt: Point) -> Option<usize> {
+        if self.contains(pt) {
+            Some(pt.y as usize * self.width + pt.x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Get the point corresponding to an index into the underlying data.
+    pub const fn point_of(&self, index: usize) -> Point {
+        Point::new((index % self.width) as isize, (index / self.width) as isize)
+    }
+
+    /// Get the value of a single point in the grid.
+    /// If it is outside the grid, `None` will be returned.
+    pub fn get(&self, pt: Point) -> Option<&T> {
+        self.index_of(pt).map(|i| &self.items[i])
+    }
+
+    /// Get a mutable reference to a single point in the grid.
+    /// If it is outside the grid, `None` will be returned.
+    pub fn get_mut(&mut self, pt: Point) -> Option<&mut T> {
+        self.index_of(pt).map(|i| &mut self.items[i])
+    }
+
+    /// Replace the value at a point, returning the old value.
+    /// If it is outside the grid, `None` will be returned and the grid is left unchanged.
+    pub fn set(&mut self, pt: Point, value: T) -> Option<T> {
+        self.get_mut(pt).map(|tile| std::mem::replace(tile, value))
+    }
+
+    /// The underlying row-major data.
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.items.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.items.iter_mut()
+    }
+
+    /// Every point in the grid, in row-major order.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width;
+        (0..self.items.len()).map(move |i| Point::new((i % width) as isize, (i / width) as isize))
+    }
+
+    /// Every point in the grid alongside its value, in row-major order.
+    pub fn enumerate(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(self.items.iter())
+    }
+
+    /// The first point (in row-major order) whose value matches the predicate.
+    pub fn position(&self, predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.items
+            .iter()
+            .position(predicate)
+            .map(|i| self.point_of(i))
+    }
+
+    /// A single row of the grid, or `None` if `y` is out of bounds.
+    pub fn row(&self, y: usize) -> Option<&[T]> {
+        (y < self.height).then(|| &self.items[y * self.width..(y + 1) * self.width])
+    }
+
+    /// Every row of the grid, from top to bottom.
+    pub fn rows(&self) -> std::slice::Chunks<'_, T> {
+        self.items.chunks(self.width)
+    }
+
+    /// A single column of the grid from top to bottom, or `None` if `x` is out of bounds.
+    pub fn column(&self, x: usize) -> Option<impl Iterator<Item = &T>> {
+        (x < self.width).then(|| self.items.iter().skip(x).step_by(self.width))
+    }
+
+    /// Every column of the grid, from left to right.
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|x| self.items.iter().skip(x).step_by(self.width))
+    }
+
+    /// Wrap a point around the edges of the grid, as if it were a torus.
+    pub const fn wrap(&self, pt: Point) -> Point {
+        Point::new(
+            pt.x.rem_euclid(self.width as isize),
+            pt.y.rem_euclid(self.height as isize),
+        )
+    }
+
+    /// The in-bounds points orthogonally adjacent to `pt`.
+    pub fn neighbours(&self, pt: Point) -> impl Iterator<Item = Point> + '_ {
+        ORTHOGONAL_TRANSLATIONS
+            .iter()
+            .map(move |t| pt + *t)
+            .filter(|p| self.contains(*p))
+    }
+
+    /// The in-bounds points surrounding `pt`, including diagonals.
+    pub fn surrounding(&self, pt: Point) -> impl Iterator<Item = Point> + '_ {
+        ALL_TRANSLATIONS
+            .iter()
+            .map(move |t| pt + *t)
+            .filter(|p| self.contains(*p))
+    }
+
+    /// Create a new grid of the same size by applying `f` to every tile.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            items: self.items.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: TryFrom<char>> FromStr for Grid<T> {
+    type Err = ParseGridError<T::Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pt: Point) -> &Self::Output {
+        self.get(pt)
+            .unwrap_or_else(|| panic!("Point {pt} is outside the grid"))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, pt: Point) -> &mut Self::Output {
+        self.get_mut(pt)
+            .unwrap_or_else(|| panic!("Point {pt} is outside the grid"))
+    }
+}
+
+/// Renders each tile with its own `Display` implementation, one row per line.
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for tile in row {
+                write!(f, "{tile}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SAMPLE: &str = "ab.
+.c.
+..d";
+
+    #[test]
+    fn parse_and_render() {
+        let grid: Grid<char> = SAMPLE.parse().unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 3));
+        assert_eq!(grid.get(Point::new(1, 1)), Some(&'c'));
+        assert_eq!(grid.get(Point::new(-1, 1)), None);
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.wrap(Point::new(-1, 4)), Point::new(2, 1));
+        assert_eq!(grid.to_string(), format!("{SAMPLE}\n"));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            "ab\nc".parse::<Grid<char>>(),
+            Err(ParseGridError::Ragged {
+                line: 2,
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!("".parse::<Grid<char>>(), Err(ParseGridError::Empty));
+
+        let digits = Grid::<u8>::parse("12\n3€").map_err(|e| match e {
+            ParseGridError::InvalidTile {
+                line,
+                column,
+                found,
+                ..
+            } => (line, column, found),
+            _ => panic!("unexpected error {e:?}"),
+        });
+        assert_eq!(digits, Err((2, 2, '€')));
+    }
+
+    #[test]
+    fn rows_columns_neighbours() {
+        let mut grid: Grid<char> = SAMPLE.parse().unwrap();
+        assert_eq!(grid.row(0), Some(&['a', 'b', '.'][..]));
+        assert_eq!(
+            grid.column(2).unwrap().collect::<String>(),
+            "..d".to_string()
+        );
+        assert_eq!(grid.columns().count(), 3);
+        assert_eq!(grid.neighbours(Point::new(0, 0)).count(), 2);
+        assert_eq!(grid.surrounding(Point::new(1, 1)).count(), 8);
+
+        assert_eq!(grid.set(Point::new(2, 0), 'e'), Some('.'));
+        assert_eq!(grid.set(Point::new(2, 5), 'e'), None);
+        grid[Point::new(0, 2)] = 'f';
+        assert_eq!(grid.position(|c| *c == 'f'), Some(Point::new(0, 2)));
+    }
+
+    #[test]
+    fn point_conversions() {
+        assert_eq!(Point::new(-1, 2).to_unsigned(), None);
+        assert_eq!(Point::new(1, 2).to_unsigned(), Some((1, 2)));
+        assert_eq!(Point::from_unsigned(usize::MAX, 0), None);
+        assert_eq!(
+            Point::new(isize::MAX, 0).checked_add(Point::new(1, 0)),
+            None
+        );
+        assert_eq!(Point::new(1, 2) * 2 - Point::new(1, 1), Point::new(1, 3));
+    }
+}
@@ -0,0 +1,107 @@
+//! Loading of puzzle inputs at runtime.
+//!
+//! Inputs are looked up, in order, from:
+//! 1. a path given as the first command-line argument (`-` reads from stdin);
+//! 2. `$AOC_INPUT_DIR/dayN.txt`, if the environment variable is set;
+//! 3. `inputs/dayN.txt` in the crate root.
+
+use std::fmt;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+/// The environment variable which overrides the directory inputs are loaded from.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+/// The directory inputs are loaded from when [`INPUT_DIR_VAR`] is not set.
+pub const DEFAULT_INPUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/inputs");
+
+/// Where a day's input is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Stdin,
+    File(PathBuf),
+}
+
+impl Source {
+    /// The default input file for a day, e.g. `inputs/day1.txt`.
+    pub fn default_for(day: &str) -> Self {
+        let dir = std::env::var_os(INPUT_DIR_VAR)
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR));
+        Self::File(dir.join(format!("{day}.txt")))
+    }
+
+    /// Interpret a command-line argument: `-` is stdin, anything else is a path.
+    pub fn from_arg(arg: &str) -> Self {
+        match arg {
+            "-" => Self::Stdin,
+            path => Self::File(path.into()),
+        }
+    }
+
+    pub fn read(&self) -> Result<String, InputError> {
+        let result = match self {
+            Self::Stdin => {
+                let mut buf = String::new();
+                io::stdin().read_to_string(&mut buf).map(|_| buf)
+            }
+            Self::File(path) => std::fs::read_to_string(path),
+        };
+        result.map_err(|error| InputError {
+            source: self.clone(),
+            error,
+        })
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stdin => write!(f, "<stdin>"),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct InputError {
+    pub source: Source,
+    pub error: io::Error,
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not read input from `{}`: {}",
+            self.source, self.error
+        )
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Get the name of a day (e.g. `day1`) from the `module_path!()` of its binary.
+pub fn day_name(module_path: &str) -> &str {
+    module_path.rsplit("::").next().unwrap_or(module_path)
+}
+
+/// Read the input for a day, leaking it so that part functions can borrow it for `'static`.
+pub fn load(source: &Source) -> Result<&'static str, InputError> {
+    source.read().map(|s| &*s.leak())
+}
+
+/// Load the input for a day, printing a readable error and exiting if it can't be read.
+pub fn load_or_exit(day: &str, source: &Source) -> &'static str {
+    load(source).unwrap_or_else(|e| {
+        eprintln!("error: {day}: {e}");
+        eprintln!(
+            "note: pass an input path (or `-` for stdin), or set {INPUT_DIR_VAR} to a directory containing {day}.txt"
+        );
+        std::process::exit(1);
+    })
+}
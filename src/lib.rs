@@ -1,55 +1,202 @@
+pub mod answers;
+pub mod bench;
+pub mod error;
+pub mod grid;
+pub mod input;
+pub mod order;
+pub mod runner;
+pub mod search;
+
+pub use error::Error;
+
 // Based on https://www.reddit.com/r/adventofcode/comments/e5sa2d/comment/f9nea6y
-/// Creates a `main()` function for each day, which automatically loads the appropriate input from `dayN.txt`.
+/// Creates a `main()` function for each day, which loads the appropriate input at runtime.
+/// See [`input`] for where the input is read from.
+///
+/// Each function may return any [`runner::IntoAnswer`] type: a number, a string,
+/// or a `Result` of one of those with an [`Error`], which is printed instead of aborting.
+///
+/// The first argument of each function must be `&'static str`, unless the function is prefixed with `byte`.
+/// If it is, `&'static [u8]` will be provided instead. Byte and string parts may be mixed freely:
+/// ```ignore
+/// day!(part1, byte part2);
+/// ```
+///
+/// Alternative implementations of a part can be listed after it with `|`, and are compared
+/// when the day is run with `--bench` (see [`bench`]). They take the same input type as their part:
+/// ```ignore
+/// day!(part1, part2 | part2_topt);
+/// ```
 ///
-/// The first argument of each function must be `&str`, unless the function is prefixed with `byte`.
-/// If it is, `&[u8]` will be provided instead.
+/// Extra commands, such as visualisations, can be listed after the parts as `views`,
+/// and are run with `dayN [INPUT] --view NAME [ARGS...]` (see [`runner::View`]).
+/// Each takes the input and the remaining arguments, and returns a `Result<(), E>`
+/// for any error type `E`:
+/// ```ignore
+/// day!(part1, part2; views: render, animate);
+/// ```
 ///
-/// ## Note
-/// Both functions must either be marked as `byte`, or not marked as `byte`.
+/// The parts are also registered in a `DAY` constant (a [`runner::Day`]),
+/// which is how the `aoc` runner binary finds them.
 #[macro_export]
 macro_rules! day {
-    ($($part:ident),+) => {
-        const INPUT: &str = include_str!(concat!(module_path!(), ".txt"));
-        fn main() {
-            $(println!("{}: {}", stringify!($part), $part(INPUT));)+
+    (@part byte $part:ident [$($alt:ident)*]) => {
+        $crate::runner::Part {
+            name: stringify!($part),
+            solve: |input: &'static str| {
+                $crate::runner::IntoAnswer::into_answer($part(input.as_bytes()))
+            },
+            alternatives: &[$($crate::day!(@part byte $alt []),)*],
+        }
+    };
+    (@part $part:ident [$($alt:ident)*]) => {
+        $crate::runner::Part {
+            name: stringify!($part),
+            solve: |input: &'static str| $crate::runner::IntoAnswer::into_answer($part(input)),
+            alternatives: &[$($crate::day!(@part $alt []),)*],
         }
     };
-    ($(byte $part:ident),+) => {
-        const INPUT: &str = include_bytes!(concat!(module_path!(), ".txt"));
+    (@parts [$($acc:tt)*] $(; views: $($view:ident),+ $(,)?)?) => {
+        pub const DAY: $crate::runner::Day = $crate::runner::Day {
+            module: module_path!(),
+            parts: &[$($acc)*],
+            views: &[$($($crate::runner::View {
+                name: stringify!($view),
+                run: |input: &'static str, args: &[String]| {
+                    $view(input, args).map_err(::std::convert::Into::into)
+                },
+            },)+)?],
+        };
+    };
+    (@parts [$($acc:tt)*] byte $part:ident $(| $alt:ident)* $(, $($rest:tt)*)?) => {
+        $crate::day!(@parts [$($acc)* $crate::day!(@part byte $part [$($alt)*]),] $($($rest)*)?);
+    };
+    (@parts [$($acc:tt)*] byte $part:ident $(| $alt:ident)* ; $($rest:tt)*) => {
+        $crate::day!(@parts [$($acc)* $crate::day!(@part byte $part [$($alt)*]),] ; $($rest)*);
+    };
+    (@parts [$($acc:tt)*] $part:ident $(| $alt:ident)* $(, $($rest:tt)*)?) => {
+        $crate::day!(@parts [$($acc)* $crate::day!(@part $part [$($alt)*]),] $($($rest)*)?);
+    };
+    (@parts [$($acc:tt)*] $part:ident $(| $alt:ident)* ; $($rest:tt)*) => {
+        $crate::day!(@parts [$($acc)* $crate::day!(@part $part [$($alt)*]),] ; $($rest)*);
+    };
+    ($($parts:tt)+) => {
+        $crate::day!(@parts [] $($parts)+);
+
+        #[allow(dead_code)]
         fn main() {
-            $(println!("{}: {}", stringify!($part), $part(INPUT));)+
+            $crate::runner::day_main(&DAY);
         }
     };
 }
 
 /// Generates a testing function (with the `#[test]` attribute already added).
-/// The format is `test_day!(fn_name -> tested_function(input), expected_output)`.
-/// * `input` may be either a literal or an identifier.
-/// * `expected_output` may be either a literal or an identifier.
+/// Any attributes (such as `#[ignore]`) written before the name are applied to it.
+///
+/// The basic format is `test_day!(fn_name -> tested_function(input), expected_output)`.
+/// * `input` may be any expression, or `file "path"` to load a fixture from `fixtures/path`.
+/// * `expected_output` may be any expression, such as `31` or `Ok(31)`,
+///   or `Err(pattern)` to check that a fallible part fails with a matching [`Error`].
+///
+/// Several cases can be checked at once with a table of `(input, expected_output)` pairs:
+/// ```ignore
+/// test_day!(test_part1 -> part1, [(SAMPLE_INPUT, Ok(11)), (file "day1/other.txt", Ok(3))]);
+/// ```
+///
+/// Finally, `test_day!(fn_name -> DAY)` checks every part of the day against its real input
+/// and the accepted answers (see [`answers`]), skipping anything that isn't available.
 #[macro_export]
 macro_rules! test_day {
-    ($name:ident -> $part:ident($input:literal), $output:literal) => {
-        #[test]
-        fn $name() {
-            assert_eq!($part($input), $output);
+    (@assert $case:expr, $actual:expr, Err($pattern:pat)) => {
+        match $actual {
+            Err($pattern) => {}
+            other => panic!(
+                "{}: expected Err({}), got {:?}",
+                $case,
+                stringify!($pattern),
+                other
+            ),
         }
     };
-    ($name:ident -> $part:ident($input:ident), $output:literal) => {
+    (@assert $case:expr, $actual:expr, $expected:expr) => {
+        assert_eq!($actual, $expected, "{}", $case);
+    };
+    (@run $case:expr, $part:ident, file $path:literal, $($expected:tt)+) => {
+        $crate::test_day!(
+            @assert $case,
+            $part(include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/", $path))),
+            $($expected)+
+        )
+    };
+    (@run $case:expr, $part:ident, $input:expr, $($expected:tt)+) => {
+        $crate::test_day!(@assert $case, $part($input), $($expected)+)
+    };
+    ($(#[$meta:meta])* $name:ident -> $day:ident) => {
+        $(#[$meta])*
         #[test]
         fn $name() {
-            assert_eq!($part($input), $output);
+            $crate::runner::check_real_input(&$day);
         }
     };
-    ($name:ident -> $part:ident($input:literal), $output:ident) => {
+    ($(#[$meta:meta])* $name:ident -> $part:ident, [$(($($case:tt)+)),+ $(,)?]) => {
+        $(#[$meta])*
         #[test]
         fn $name() {
-            assert_eq!($part($input), $output);
+            $($crate::test_day!(@run stringify!(($($case)+)), $part, $($case)+);)+
         }
     };
-    ($name:ident -> $part:ident($input:ident), $output:ident) => {
+    ($(#[$meta:meta])* $name:ident -> $part:ident($($input:tt)+), $($expected:tt)+) => {
+        $(#[$meta])*
         #[test]
         fn $name() {
-            assert_eq!($part($input), $output);
+            $crate::test_day!(@run stringify!($name), $part, $($input)+, $($expected)+);
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    mod mixed_day {
+        crate::day!(part1, byte part2; views: lines);
+
+        fn part1(input: &'static str) -> usize {
+            input.lines().count()
+        }
+
+        fn part2(input: &'static [u8]) -> usize {
+            input.iter().filter(|b| **b == b'#').count()
+        }
+
+        fn lines(input: &'static str, args: &[String]) -> Result<(), crate::Error> {
+            if args.is_empty() {
+                return Err(crate::Error::EmptyInput);
+            }
+            assert_eq!(input.lines().count(), args.len());
+            Ok(())
+        }
+
+        #[test]
+        fn runs_str_and_byte_parts() {
+            let answers: Vec<_> = DAY
+                .parts
+                .iter()
+                .map(|part| (part.name, (part.solve)("#.\n##\n")))
+                .collect();
+            assert_eq!(
+                answers,
+                [
+                    ("part1", Ok("2".to_string())),
+                    ("part2", Ok("3".to_string()))
+                ]
+            );
+        }
+
+        #[test]
+        fn runs_views() {
+            let view = DAY.view("lines").unwrap();
+            assert!((view.run)("#.\n##\n", &["a".into(), "b".into()]).is_ok());
+            let e = (view.run)("#.\n##\n", &[]).unwrap_err();
+            assert_eq!(e.to_string(), crate::Error::EmptyInput.to_string());
+        }
+    }
+}
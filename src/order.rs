@@ -0,0 +1,233 @@
+//! Orderings defined by precedence rules, such as "page X must be printed before page Y".
+//!
+//! ```ignore
+//! let order = RuleOrder::new([(47, 53), (97, 47)]);
+//! let mut pages = vec![53, 47, 97];
+//! let comparator = order.comparator(&pages)?;
+//! pages.sort_by(|a, b| comparator.compare(a, b));
+//! assert_eq!(pages, [97, 47, 53]);
+//! ```
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::Hash;
+
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// A set of rules saying that one item must come before another.
+///
+/// The rules needn't be consistent as a whole: only the rules between the items being
+/// ordered are used, and ordering fails with a [`Cycle`] if those contradict each other.
+#[derive(Debug, Clone)]
+pub struct RuleOrder<T> {
+    /// The items which must come after each item.
+    after: FxHashMap<T, FxHashSet<T>>,
+}
+
+/// A cycle of rules which contradict each other, e.g. `a` before `b` before `a`.
+/// Each item must come before the next, and the last before the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<T>(pub Vec<T>);
+
+impl<T: fmt::Display> fmt::Display for Cycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some((first, rest)) = self.0.split_first() else {
+            return write!(f, "the rules form a cycle");
+        };
+        write!(f, "the rules form a cycle: {first}")?;
+        for item in rest.iter().chain([first]) {
+            write!(f, " -> {item}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug + fmt::Display> std::error::Error for Cycle<T> {}
+
+impl<T: Hash + Eq> RuleOrder<T> {
+    /// Build the rules from `(before, after)` pairs.
+    pub fn new(pairs: impl IntoIterator<Item = (T, T)>) -> Self {
+        let mut after: FxHashMap<T, FxHashSet<T>> = FxHashMap::default();
+        for (a, b) in pairs {
+            after.entry(a).or_default().insert(b);
+        }
+        Self { after }
+    }
+
+    /// Whether a rule says that `a` must come before `b`.
+    pub fn precedes(&self, a: &T, b: &T) -> bool {
+        self.after.get(a).is_some_and(|after| after.contains(b))
+    }
+
+    /// Every rule, as `(before, after)` pairs, in no particular order.
+    pub fn pairs(&self) -> impl Iterator<Item = (&T, &T)> {
+        self.after
+            .iter()
+            .flat_map(|(a, after)| after.iter().map(move |b| (a, b)))
+    }
+
+    /// Whether every rule between the items is satisfied, checked in a single pass:
+    /// no item may need to come before one which has already been seen.
+    pub fn is_ordered(&self, items: &[T]) -> bool {
+        let mut seen = FxHashSet::default();
+        for item in items {
+            if let Some(after) = self.after.get(item) {
+                if after.iter().any(|a| seen.contains(a)) {
+                    return false;
+                }
+            }
+            seen.insert(item);
+        }
+        true
+    }
+
+    /// The first rule broken by the items, as `(before, after)`: the first item which
+    /// should have come before an earlier one, and the earliest such item.
+    pub fn first_violation<'a>(&self, items: &'a [T]) -> Option<(&'a T, &'a T)> {
+        items.iter().enumerate().find_map(|(i, item)| {
+            items[..i]
+                .iter()
+                .find(|earlier| self.precedes(item, earlier))
+                .map(|earlier| (item, earlier))
+        })
+    }
+
+    /// The positions of the items in an order which satisfies every rule between them,
+    /// using Kahn's algorithm. Unconstrained items keep their relative order.
+    fn kahn(&self, items: &[T]) -> Result<Vec<usize>, Vec<usize>> {
+        let mut incoming: Vec<usize> = items
+            .iter()
+            .map(|b| items.iter().filter(|a| self.precedes(a, b)).count())
+            .collect();
+        let mut order = Vec::with_capacity(items.len());
+        let mut done = vec![false; items.len()];
+
+        while let Some(i) = (0..items.len()).find(|&i| !done[i] && incoming[i] == 0) {
+            done[i] = true;
+            order.push(i);
+            for (j, b) in items.iter().enumerate() {
+                if self.precedes(&items[i], b) {
+                    incoming[j] -= 1;
+                }
+            }
+        }
+
+        if order.len() == items.len() {
+            Ok(order)
+        } else {
+            Err(self.find_cycle(items, &done))
+        }
+    }
+
+    /// Find a cycle among the items which Kahn's algorithm couldn't place.
+    /// Each of them has a predecessor which is also unplaced, so walking
+    /// backwards from any one must eventually repeat an item.
+    fn find_cycle(&self, items: &[T], done: &[bool]) -> Vec<usize> {
+        let stuck: Vec<usize> = (0..items.len()).filter(|&i| !done[i]).collect();
+        let mut path = vec![stuck[0]];
+        loop {
+            let current = *path.last().unwrap();
+            let previous = *stuck
+                .iter()
+                .find(|&&a| self.precedes(&items[a], &items[current]))
+                .expect("an unplaced item should have an unplaced predecessor");
+            if let Some(start) = path.iter().position(|&p| p == previous) {
+                let mut cycle = path.split_off(start);
+                cycle.reverse();
+                return cycle;
+            }
+            path.push(previous);
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> RuleOrder<T> {
+    /// Order the items so that every rule between them is satisfied.
+    /// Items which aren't constrained keep their relative order.
+    pub fn sort(&self, items: &[T]) -> Result<Vec<T>, Cycle<T>> {
+        match self.kahn(items) {
+            Ok(order) => Ok(order.into_iter().map(|i| items[i].clone()).collect()),
+            Err(cycle) => Err(Cycle(cycle.into_iter().map(|i| items[i].clone()).collect())),
+        }
+    }
+
+    /// A comparator for sorting `items` (or any subset of them) with [`slice::sort_by`].
+    /// Fails if the rules between the items contradict each other.
+    pub fn comparator(&self, items: &[T]) -> Result<Comparator<T>, Cycle<T>> {
+        let sorted = self.sort(items)?;
+        Ok(Comparator {
+            rank: sorted
+                .into_iter()
+                .enumerate()
+                .map(|(rank, item)| (item, rank))
+                .collect(),
+        })
+    }
+}
+
+/// A total order which is consistent with a [`RuleOrder`], from [`RuleOrder::comparator`].
+#[derive(Debug, Clone)]
+pub struct Comparator<T> {
+    rank: FxHashMap<T, usize>,
+}
+
+impl<T: Hash + Eq> Comparator<T> {
+    /// Compare two items.
+    ///
+    /// ## Panics
+    /// Panics if either item wasn't given when the comparator was created.
+    pub fn compare(&self, a: &T, b: &T) -> Ordering {
+        let rank = |item| {
+            *self
+                .rank
+                .get(item)
+                .expect("can only compare items the comparator was created for")
+        };
+        rank(a).cmp(&rank(b))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sort_by_comparator() {
+        let order = RuleOrder::new([
+            ("socks", "shoes"),
+            ("trousers", "shoes"),
+            ("pants", "trousers"),
+        ]);
+        let mut clothes = vec!["shoes", "hat", "trousers", "socks", "pants"];
+        assert!(!order.is_ordered(&clothes));
+        assert_eq!(
+            order.first_violation(&clothes),
+            Some((&"trousers", &"shoes"))
+        );
+
+        let comparator = order.comparator(&clothes).unwrap();
+        clothes.sort_by(|a, b| comparator.compare(a, b));
+        assert_eq!(clothes, ["hat", "socks", "pants", "trousers", "shoes"]);
+        assert!(order.is_ordered(&clothes));
+        assert_eq!(order.sort(&["shoes", "socks"]), Ok(vec!["socks", "shoes"]));
+    }
+
+    #[test]
+    fn contradictions() {
+        let order = RuleOrder::new([(1, 2), (2, 3), (3, 1), (3, 4)]);
+        // The rules only need to be consistent between the items being ordered.
+        assert_eq!(order.sort(&[4, 2, 3]), Ok(vec![2, 3, 4]));
+
+        let cycle = order.comparator(&[4, 1, 2, 3]).unwrap_err();
+        assert_eq!(cycle, Cycle(vec![1, 2, 3]));
+        assert_eq!(
+            cycle.to_string(),
+            "the rules form a cycle: 1 -> 2 -> 3 -> 1"
+        );
+        assert_eq!(
+            RuleOrder::new([(5, 5)]).sort(&[5]).unwrap_err().to_string(),
+            "the rules form a cycle: 5 -> 5"
+        );
+        assert_eq!(Cycle::<u32>(vec![]).to_string(), "the rules form a cycle");
+    }
+}
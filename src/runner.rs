@@ -0,0 +1,517 @@
+//! Registration of days and their parts, shared by each day's own binary and the `aoc` runner.
+
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+use crate::answers::{self, Answers, Check};
+use crate::bench;
+use crate::input::{self, Source};
+use crate::Error;
+
+/// A value which can be returned from a part function.
+///
+/// This is implemented for the primitive types and strings, and for `Result<T, Error>`
+/// where `T` is any of those, so that parts may either panic or return errors.
+pub trait IntoAnswer {
+    fn into_answer(self) -> Result<String, Error>;
+}
+
+macro_rules! impl_into_answer {
+    ($($ty:ty),+) => {
+        $(impl IntoAnswer for $ty {
+            fn into_answer(self) -> Result<String, Error> {
+                Ok(self.to_string())
+            }
+        })+
+    };
+}
+
+impl_into_answer!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, char, String, &str
+);
+
+impl<T: IntoAnswer> IntoAnswer for Result<T, Error> {
+    fn into_answer(self) -> Result<String, Error> {
+        self?.into_answer()
+    }
+}
+
+/// A single part of a day, as registered by [`day!`](crate::day).
+#[derive(Debug, Clone, Copy)]
+pub struct Part {
+    pub name: &'static str,
+    /// Solve the part, formatting its answer as a `String`.
+    pub solve: fn(&'static str) -> Result<String, Error>,
+    /// Other implementations of the same part, for comparison with [`bench`].
+    pub alternatives: &'static [Part],
+}
+
+impl Part {
+    /// Solve the part, measuring how long it took.
+    pub fn run(&self, input: &'static str) -> (Result<String, Error>, Duration) {
+        let start = Instant::now();
+        let answer = (self.solve)(input);
+        (answer, start.elapsed())
+    }
+
+    /// The number of this part, from its name (e.g. `2` for `part2`).
+    pub fn number(&self) -> Option<u32> {
+        number_in(self.name)
+    }
+}
+
+/// The result of running a [`View`].
+pub type ViewResult = Result<(), Box<dyn std::error::Error>>;
+
+/// An extra command of a day, such as a visualisation, run with `dayN --view NAME [ARGS]`.
+#[derive(Debug, Clone, Copy)]
+pub struct View {
+    pub name: &'static str,
+    /// Run the view against an input, with any arguments given after its name.
+    pub run: fn(&'static str, &[String]) -> ViewResult,
+}
+
+/// A day and all of its parts, as registered by [`day!`](crate::day).
+#[derive(Debug, Clone, Copy)]
+pub struct Day {
+    /// The `module_path!()` of the day, e.g. `day1` or `aoc::day1`.
+    pub module: &'static str,
+    pub parts: &'static [Part],
+    pub views: &'static [View],
+}
+
+impl Day {
+    /// The name of the day, e.g. `day1`.
+    pub fn name(&self) -> &'static str {
+        input::day_name(self.module)
+    }
+
+    /// The number of the day, e.g. `1`.
+    pub fn number(&self) -> Option<u32> {
+        number_in(self.name())
+    }
+
+    pub fn view(&self, name: &str) -> Option<&View> {
+        self.views.iter().find(|v| v.name == name)
+    }
+}
+
+/// The first run of digits in a name, e.g. `2` in `part2_topt`.
+fn number_in(name: &str) -> Option<u32> {
+    let digits = &name[name.find(|c: char| c.is_ascii_digit())?..];
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// The command-line arguments accepted by a single day's binary:
+/// `dayN [INPUT] [--bench [ITERATIONS]] [--accept] [--view NAME [ARGS...]]`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DayArgs {
+    pub source: Option<Source>,
+    pub bench: Option<usize>,
+    /// Store the answers as correct in the [`answers`] file.
+    pub accept: bool,
+    /// The [`View`] to run instead of solving the parts.
+    pub view: Option<String>,
+    /// Every argument after the view's name, which are passed to the view.
+    pub view_args: Vec<String>,
+}
+
+impl DayArgs {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--bench" => {
+                    let iterations = match args.next_if(|a| !a.starts_with("--")) {
+                        Some(n) => n
+                            .parse()
+                            .map_err(|_| format!("invalid iteration count `{n}`"))?,
+                        None => bench::DEFAULT_ITERATIONS,
+                    };
+                    parsed.bench = Some(iterations);
+                }
+                "--accept" => parsed.accept = true,
+                "--view" => {
+                    let name = args.next().ok_or("expected a view name after `--view`")?;
+                    parsed.view = Some(name);
+                    parsed.view_args = args.by_ref().collect();
+                }
+                flag if flag.starts_with("--") => return Err(format!("unknown flag `{flag}`")),
+                _ if parsed.source.is_some() => return Err(format!("unexpected argument `{arg}`")),
+                path => parsed.source = Some(Source::from_arg(path)),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Load the answers file, printing a readable error and exiting if it is invalid.
+pub fn load_answers_or_exit() -> Answers {
+    Answers::load().unwrap_or_else(|e| {
+        eprintln!("error: could not load `{}`: {e}", answers::path().display());
+        std::process::exit(1);
+    })
+}
+
+/// Save the answers file, printing a readable error and exiting if it can't be written.
+pub fn save_answers_or_exit(answers: &Answers) {
+    if let Err(e) = answers.save() {
+        eprintln!(
+            "error: could not write `{}`: {e}",
+            answers::path().display()
+        );
+        std::process::exit(1);
+    }
+}
+
+/// The entry point of a single day's binary: solve every part and print the answers,
+/// benchmark them if `--bench` was given, or run a [`View`] if `--view` was given.
+///
+/// When run against the default input, answers are checked against the [`answers`] file,
+/// and the process exits with an error if any of them have changed.
+pub fn day_main(day: &Day) {
+    let usage_error = |e: &str| -> ! {
+        eprintln!("error: {e}");
+        eprintln!(
+            "usage: {} [INPUT | -] [--bench [ITERATIONS]] [--accept] [--view NAME [ARGS...]]",
+            day.name()
+        );
+        if !day.views.is_empty() {
+            let names: Vec<_> = day.views.iter().map(|v| v.name).collect();
+            eprintln!("views: {}", names.join(", "));
+        }
+        std::process::exit(2);
+    };
+    let args = DayArgs::parse(std::env::args().skip(1)).unwrap_or_else(|e| usage_error(&e));
+    // Accepted answers only apply to the real input.
+    let verify = args.source.is_none();
+    if args.accept && !verify {
+        usage_error("--accept can only be used with the default input");
+    }
+
+    let source = args
+        .source
+        .unwrap_or_else(|| Source::default_for(day.name()));
+    let input = input::load_or_exit(day.name(), &source);
+
+    if let Some(name) = &args.view {
+        if args.bench.is_some() || args.accept {
+            usage_error("--view can't be combined with --bench or --accept");
+        }
+        let view = day
+            .view(name)
+            .unwrap_or_else(|| usage_error(&format!("unknown view `{name}`")));
+        if let Err(e) = (view.run)(input, &args.view_args) {
+            eprintln!("error: {}: {e}", view.name);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if let Some(iterations) = args.bench {
+        let mut consistent = true;
+        for part in day.parts {
+            let report = bench::bench_part(part, input, iterations);
+            consistent &= report.is_consistent();
+            println!("{report}");
+        }
+        if !consistent {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let mut answers = if verify {
+        load_answers_or_exit()
+    } else {
+        Answers::default()
+    };
+    let mut incorrect = false;
+    let mut failed = false;
+    for part in day.parts {
+        let answer = match (part.solve)(input) {
+            Ok(answer) => answer,
+            Err(e) => {
+                println!("{}: error: {e}", part.name);
+                failed = true;
+                continue;
+            }
+        };
+        if verify {
+            let check = answers.check(day.name(), part.name, &answer);
+            incorrect |= check.is_incorrect();
+            println!("{}: {} [{check}]", part.name, answer);
+            if args.accept {
+                answers.insert(day.name(), part.name, answer);
+            }
+        } else {
+            println!("{}: {}", part.name, answer);
+        }
+    }
+
+    if args.accept {
+        save_answers_or_exit(&answers);
+    }
+    if failed || (incorrect && !args.accept) {
+        std::process::exit(1);
+    }
+}
+
+/// Check every part of a day against the accepted answers for its real input, panicking on a mismatch.
+/// This backs `test_day!(name -> DAY)`; days without an input and parts without an answer are skipped.
+pub fn check_real_input(day: &Day) {
+    let input = match input::load(&Source::default_for(day.name())) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("skipping {}: {e}", day.name());
+            return;
+        }
+    };
+    let answers = Answers::load()
+        .unwrap_or_else(|e| panic!("could not load `{}`: {e}", answers::path().display()));
+
+    for part in day.parts {
+        let Some(expected) = answers.get(day.name(), part.name) else {
+            eprintln!("skipping {}: no accepted answer", part.name);
+            continue;
+        };
+        match (part.solve)(input) {
+            Ok(answer) => assert_eq!(
+                answer, expected,
+                "{} no longer matches the accepted answer",
+                part.name
+            ),
+            Err(e) => panic!("{} failed on the real input: {e}", part.name),
+        }
+    }
+}
+
+/// Which days the `aoc` runner should run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selection {
+    All,
+    Days(Vec<RangeInclusive<u32>>),
+}
+
+impl Selection {
+    /// Parse a comma-separated list of days or inclusive ranges, e.g. `1,3-5,7..=8`, or `all`.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s == "all" {
+            return Some(Self::All);
+        }
+
+        let ranges = s
+            .split(',')
+            .map(|item| {
+                let bounds = item.split_once("..=").or_else(|| item.split_once('-'));
+                match bounds {
+                    Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+                    None => {
+                        let day = item.parse().ok()?;
+                        Some(day..=day)
+                    }
+                }
+            })
+            .collect::<Option<_>>()?;
+        Some(Self::Days(ranges))
+    }
+
+    pub fn contains(&self, day: u32) -> bool {
+        match self {
+            Self::All => true,
+            Self::Days(ranges) => ranges.iter().any(|r| r.contains(&day)),
+        }
+    }
+}
+
+/// One line of output from the `aoc` runner.
+#[derive(Debug, Clone)]
+pub struct Row {
+    pub day: &'static str,
+    pub part: &'static str,
+    pub answer: Result<String, String>,
+    pub time: Option<Duration>,
+    pub check: Option<Check>,
+}
+
+/// Run every selected part of every selected day, loading each day's input from its default source
+/// and checking each answer against `answers`.
+/// A day whose input can't be loaded produces a single row containing the error.
+pub fn run_days(
+    days: &[Day],
+    selection: &Selection,
+    part: Option<u32>,
+    answers: &Answers,
+) -> Vec<Row> {
+    let mut rows = vec![];
+    for day in days {
+        if !day.number().is_some_and(|n| selection.contains(n)) {
+            continue;
+        }
+
+        let input = match input::load(&Source::default_for(day.name())) {
+            Ok(input) => input,
+            Err(e) => {
+                rows.push(Row {
+                    day: day.name(),
+                    part: "-",
+                    answer: Err(e.to_string()),
+                    time: None,
+                    check: None,
+                });
+                continue;
+            }
+        };
+
+        for p in day.parts {
+            if part.is_some_and(|n| p.number() != Some(n)) {
+                continue;
+            }
+            let (answer, time) = p.run(input);
+            rows.push(Row {
+                day: day.name(),
+                part: p.name,
+                check: answer
+                    .as_ref()
+                    .ok()
+                    .map(|answer| answers.check(day.name(), p.name, answer)),
+                answer: answer.map_err(|e| e.to_string()),
+                time: Some(time),
+            });
+        }
+    }
+    rows
+}
+
+/// Renders rows as an aligned table, followed by the total time taken.
+pub struct Table<'a>(pub &'a [Row]);
+
+impl fmt::Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<[String; 5]> = self
+            .0
+            .iter()
+            .map(|row| {
+                [
+                    row.day.to_string(),
+                    row.part.to_string(),
+                    match &row.answer {
+                        Ok(answer) => answer.clone(),
+                        Err(e) => format!("error: {e}"),
+                    },
+                    row.time.map(|t| format!("{t:.2?}")).unwrap_or_default(),
+                    row.check
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .unwrap_or_default(),
+                ]
+            })
+            .collect();
+
+        let header = ["Day", "Part", "Answer", "Time", "Check"].map(String::from);
+        let mut widths = header.each_ref().map(String::len);
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String; 5]| {
+            writeln!(
+                f,
+                "{:<w0$} | {:<w1$} | {:<w2$} | {:>w3$} | {}",
+                row[0],
+                row[1],
+                row[2],
+                row[3],
+                row[4],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+                w3 = widths[3],
+            )
+        };
+
+        write_row(f, &header)?;
+        writeln!(f, "{}", widths.map(|w| "-".repeat(w)).join("-+-"))?;
+        for row in &cells {
+            write_row(f, row)?;
+        }
+
+        let total: Duration = self.0.iter().filter_map(|row| row.time).sum();
+        writeln!(f)?;
+        write!(f, "Total time: {total:.2?}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_selection() {
+        assert_eq!(Selection::parse("all"), Some(Selection::All));
+        assert_eq!(
+            Selection::parse("1,3-5,7..=8"),
+            Some(Selection::Days(vec![1..=1, 3..=5, 7..=8]))
+        );
+        assert_eq!(Selection::parse("1,x"), None);
+
+        let selection = Selection::parse("2-4").unwrap();
+        assert!(!selection.contains(1));
+        assert!(selection.contains(3));
+    }
+
+    #[test]
+    fn day_and_part_numbers() {
+        let day = Day {
+            module: "aoc::day12",
+            parts: &[],
+            views: &[],
+        };
+        assert_eq!(day.name(), "day12");
+        assert_eq!(day.number(), Some(12));
+        assert_eq!(number_in("part2_topt"), Some(2));
+    }
+
+    #[test]
+    fn parse_day_args() {
+        let parse = |args: &[&str]| DayArgs::parse(args.iter().map(|a| a.to_string()));
+
+        assert_eq!(parse(&[]), Ok(DayArgs::default()));
+        assert_eq!(
+            parse(&["-", "--bench"]),
+            Ok(DayArgs {
+                source: Some(Source::Stdin),
+                bench: Some(bench::DEFAULT_ITERATIONS),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["--bench", "10", "in.txt", "--accept"]),
+            Ok(DayArgs {
+                source: Some(Source::File("in.txt".into())),
+                bench: Some(10),
+                accept: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse(&["in.txt", "--view", "path", "--bench", "x"]),
+            Ok(DayArgs {
+                source: Some(Source::File("in.txt".into())),
+                view: Some("path".to_string()),
+                view_args: vec!["--bench".to_string(), "x".to_string()],
+                ..Default::default()
+            })
+        );
+        assert!(parse(&["--view"]).is_err());
+        assert!(parse(&["a.txt", "b.txt"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+//! Word searches: finding words written in a straight line in any direction on a grid of letters.
+//!
+//! ```ignore
+//! let grid: Grid<char> = "XMAS\n.A..\nS.S.".parse()?;
+//! let search = WordSearch::new(&grid);
+//! let matches = search.find("XAS");
+//! assert_eq!(matches, [Match { start: Point::new(0, 0), direction: Point::new(1, 1), len: 3 }]);
+//! assert_eq!(search.render(&matches).to_string(), "X...\n.A..\n..S.\n");
+//! ```
+
+use crate::grid::{Grid, Point, ALL_TRANSLATIONS};
+
+/// A word found in a [`WordSearch`], reading from `start` in steps of `direction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Match {
+    pub start: Point,
+    pub direction: Point,
+    /// The length of the word in letters.
+    pub len: usize,
+}
+
+/// Searches a grid of letters for words, in each of a set of directions.
+#[derive(Debug, Clone, Copy)]
+pub struct WordSearch<'a> {
+    grid: &'a Grid<char>,
+    directions: &'a [Point],
+    wrap: bool,
+    ignore_case: bool,
+}
+
+impl<'a> WordSearch<'a> {
+    /// Search in all eight directions, without wrapping and matching case exactly.
+    pub fn new(grid: &'a Grid<char>) -> Self {
+        Self {
+            grid,
+            directions: &ALL_TRANSLATIONS,
+            wrap: false,
+            ignore_case: false,
+        }
+    }
+
+    /// Only search in these directions.
+    pub fn directions(self, directions: &'a [Point]) -> Self {
+        Self { directions, ..self }
+    }
+
+    /// Whether words can continue past one edge of the grid onto the opposite edge.
+    pub fn wrapping(self, wrap: bool) -> Self {
+        Self { wrap, ..self }
+    }
+
+    /// Whether upper and lower case letters match each other.
+    pub fn ignore_case(self, ignore_case: bool) -> Self {
+        Self {
+            ignore_case,
+            ..self
+        }
+    }
+
+    /// The points which a match covers, in the order of its letters.
+    pub fn points(&self, m: Match) -> impl Iterator<Item = Point> + '_ {
+        (0..m.len as isize).map(move |i| {
+            let pt = m.start + m.direction * i;
+            if self.wrap {
+                self.grid.wrap(pt)
+            } else {
+                pt
+            }
+        })
+    }
+
+    fn matches_at(&self, word: &[char], start: Point, direction: Point) -> bool {
+        let m = Match {
+            start,
+            direction,
+            len: word.len(),
+        };
+        self.points(m).zip(word).all(|(pt, &letter)| {
+            self.grid.get(pt).is_some_and(|&found| {
+                found == letter
+                    || (self.ignore_case && found.to_lowercase().eq(letter.to_lowercase()))
+            })
+        })
+    }
+
+    /// Every occurrence of `word`, in row-major order of their starting points.
+    /// A word of a single letter is only matched once at each point, in the first direction.
+    pub fn find(&self, word: &str) -> Vec<Match> {
+        let word: Vec<char> = word.chars().collect();
+        let directions = match word.len() {
+            0 => return vec![],
+            1 => self.directions.get(..1).unwrap_or(&[]),
+            _ => self.directions,
+        };
+
+        let mut matches = vec![];
+        for start in self.grid.points() {
+            for &direction in directions {
+                if self.matches_at(&word, start, direction) {
+                    matches.push(Match {
+                        start,
+                        direction,
+                        len: word.len(),
+                    });
+                }
+            }
+        }
+        matches
+    }
+
+    /// The grid with every letter which isn't part of a match replaced by `.`.
+    pub fn render(&self, matches: &[Match]) -> Grid<char> {
+        let mut rendered = self.grid.map(|_| '.');
+        for &m in matches {
+            for pt in self.points(m) {
+                rendered[pt] = self.grid[pt];
+            }
+        }
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn find_words() {
+        let grid: Grid<char> = "XMAS\n.A..\nS.Sx".parse().unwrap();
+        let search = WordSearch::new(&grid);
+        assert_eq!(
+            search.find("SAM"),
+            [Match {
+                start: Point::new(3, 0),
+                direction: Point::new(-1, 0),
+                len: 3
+            }]
+        );
+        assert_eq!(search.find("XAS").len(), 1);
+        assert_eq!(search.find("x").len(), 1);
+        assert_eq!(search.ignore_case(true).find("x").len(), 2);
+        assert_eq!(search.find(""), []);
+        assert_eq!(search.directions(&[]).find("x"), []);
+        assert_eq!(
+            search.render(&search.find("SAM")).to_string(),
+            ".MAS\n....\n....\n"
+        );
+
+        // With wrapping, `SX` continues from the right edge onto the left,
+        // and from the bottom edge onto the top.
+        assert_eq!(search.find("SX"), []);
+        let wrapping = search.wrapping(true);
+        let directions: Vec<_> = wrapping.find("SX").iter().map(|m| m.direction).collect();
+        assert_eq!(directions, [Point::new(1, 0), Point::new(0, 1)]);
+        assert_eq!(
+            wrapping.render(&wrapping.find("SX")).to_string(),
+            "X..S\n....\nS...\n"
+        );
+
+        let across = [Point::new(1, 0)];
+        assert_eq!(wrapping.directions(&across).find("SAM"), []);
+    }
+}